@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+update problems set memory_limit = memory_limit / 1024;
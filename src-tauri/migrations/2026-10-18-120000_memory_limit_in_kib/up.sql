@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- memory limit is now enforced by the runner in KiB, the stored values are in MiB
+update problems set memory_limit = memory_limit * 1024;
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+alter table problems drop column interactive;
+alter table problems drop column interactor;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+alter table problems add column interactive boolean not null default false;
+alter table problems add column interactor text null; -- id of the program in checker table
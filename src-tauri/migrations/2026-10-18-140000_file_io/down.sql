@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+alter table problems drop column input_file;
+alter table problems drop column output_file;
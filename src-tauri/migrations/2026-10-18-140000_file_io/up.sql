@@ -0,0 +1,3 @@
+-- Your SQL goes here
+alter table problems add column input_file text null; -- read the input from this file instead of stdin
+alter table problems add column output_file text null; -- write the output to this file instead of stdout
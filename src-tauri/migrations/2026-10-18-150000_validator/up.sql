@@ -0,0 +1,2 @@
+-- Your SQL goes here
+alter table problems add column validator text null; -- id of the validator program in the checker table
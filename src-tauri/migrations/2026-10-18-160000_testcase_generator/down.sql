@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+alter table test_cases drop column generator;
+alter table test_cases drop column generator_args;
+alter table test_cases drop column reference;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+alter table test_cases add column generator text null; -- id of the solution printing the input
+alter table test_cases add column generator_args text null;
+alter table test_cases add column reference text null; -- id of the solution printing the answer
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS idx_runs_testcase_id;
+DROP INDEX IF EXISTS idx_runs_solution_id;
+DROP TABLE IF EXISTS runs;
@@ -0,0 +1,17 @@
+-- Your SQL goes here
+CREATE TABLE runs (
+    id TEXT NOT NULL PRIMARY KEY,
+    solution_id TEXT NOT NULL,
+    testcase_id TEXT NOT NULL,
+    source_hash TEXT NOT NULL, -- hash of the source the solution is judged with
+    verdict TEXT NOT NULL, -- result of the verdict, like AC
+    time_millis INTEGER NULL,
+    memory_kib INTEGER NULL,
+    checker_message TEXT NULL,
+    output_file TEXT NULL, -- name of the file in the runs folder of the workspace
+    create_datetime TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (solution_id) REFERENCES solutions (id) ON DELETE CASCADE,
+    FOREIGN KEY (testcase_id) REFERENCES test_cases (id) ON DELETE CASCADE
+);
+CREATE INDEX idx_runs_solution_id ON runs (solution_id, create_datetime);
+CREATE INDEX idx_runs_testcase_id ON runs (testcase_id, create_datetime);
@@ -11,7 +11,10 @@ use crate::{
         GetProblemsResult,
     },
     document::DocumentRepo,
-    model::{Problem, ProblemChangeset, Solution, SolutionChangeset, TestCase},
+    model::{
+        Checker, Problem, ProblemChangeset, Solution, SolutionChangeset, TestCase,
+        TestCaseChangeset,
+    },
     runner::BUNDLED_CHECKER_NAME,
 };
 use log::{error, trace, warn};
@@ -134,6 +137,12 @@ pub async fn create_checker(
     db.create_checker(params).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+#[specta::specta]
+pub async fn get_checkers(db: State<'_, DatabaseRepo>) -> Result<Vec<Checker>, String> {
+    db.get_checkers().map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn get_problem(
@@ -165,6 +174,18 @@ pub async fn delete_testcase(
     db.delete_testcase(&testcase_id).map_err(|e| e.to_string())
 }
 
+/// Set the generator recipe of a testcase, the input and the answer are not regenerated
+#[tauri::command]
+#[specta::specta]
+pub async fn update_testcase(
+    testcase_id: String,
+    params: TestCaseChangeset,
+    db: State<'_, DatabaseRepo>,
+) -> Result<(), String> {
+    db.update_testcase(&testcase_id, params)
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn get_testcases(
@@ -285,8 +306,10 @@ pub async fn resolve_checker(app: tauri::AppHandle, name: String) -> Result<Path
         trace!("resolved checker {} to {:?}", &name, &path);
         Ok(path)
     } else {
-        //TODO: custom checker with testlib
-        unimplemented!()
+        Err(format!(
+            "{} is not a bundled checker, custom checkers are compiled before judging",
+            name
+        ))
     }
 }
 
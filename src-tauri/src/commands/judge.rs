@@ -0,0 +1,855 @@
+use std::collections::HashMap;
+
+use log::{trace, warn};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri::{path::BaseDirectory, Manager, State};
+use tauri_specta::Event;
+
+use crate::{
+    commands::{
+        database::{get_string_of_doc, resolve_checker},
+        runner::{
+            get_default_env, get_output_limit_bytes, ProgramOutputEvent, ProgramOutputSource,
+            TaskRegistry,
+        },
+        QueryClientInvalidateEvent,
+    },
+    database::{CreateRunParams, DatabaseRepo, GetRunsParams},
+    document::DocumentRepo,
+    judge::{
+        self,
+        cache::{self, CompileCache},
+        generate::{self, GenerateOutcome, GenerateTask},
+        minimize::{self, MinimizeOutcome, MinimizeTask},
+        stress::{self, StressOutcome, StressProgram, StressProgress, StressTask},
+        validate::TestcaseValidation,
+        CheckerProgram, FileIo, JudgeEnv, JudgeMode, JudgeProgress, Submission, TestcaseData,
+        Verdict,
+    },
+    model::{Run, TestCase},
+    runner::{cancel::CancelToken, run::RunLimit, temp_dir, BUNDLED_CHECKER_NAME},
+};
+
+/// Everything needed to judge a solution, loaded from the workspace
+struct JudgeTask {
+    judge_env: JudgeEnv,
+    submission: Submission,
+    problem_id: String,
+    mode: JudgeMode,
+    limit: RunLimit,
+}
+
+/// Load the source of a solution, along with the id of its problem
+async fn load_solution_submission(
+    db: &State<'_, DatabaseRepo>,
+    repo: &State<'_, DocumentRepo>,
+    solution_id: &str,
+) -> Result<(String, Submission), String> {
+    let solution = db.get_solution(solution_id).map_err(|e| e.to_string())?;
+    let language = db
+        .get_language_item(&solution.language)
+        .map_err(|e| e.to_string())?;
+    let document = solution.document.ok_or("Solution has no document")?;
+    let source =
+        get_string_of_doc(document.id, "content".to_string(), db.clone(), repo.clone()).await?;
+    Ok((solution.problem_id, Submission { language, source }))
+}
+
+/// Compile with the named profile of the language instead of its `cmd_compile`
+fn apply_compile_profile(submission: &mut Submission, profile: Option<&str>) -> Result<(), String> {
+    if let Some(profile) = profile {
+        submission.language.cmd_compile = submission
+            .language
+            .compile_profiles
+            .get(profile)
+            .ok_or(format!("Compile profile {} is not found", profile))?
+            .clone();
+    }
+    Ok(())
+}
+
+/// Load the source of a checker or interactor stored in the `checker` table
+async fn load_checker_submission(
+    db: &State<'_, DatabaseRepo>,
+    repo: &State<'_, DocumentRepo>,
+    checker_id: &str,
+) -> Result<Submission, String> {
+    let checker = db.get_checker(checker_id).map_err(|e| e.to_string())?;
+    let language = db
+        .get_language_item(&checker.language)
+        .map_err(|e| e.to_string())?;
+    let source = get_string_of_doc(
+        checker.document_id,
+        "content".to_string(),
+        db.clone(),
+        repo.clone(),
+    )
+    .await?;
+    Ok(Submission { language, source })
+}
+
+/// `testlib.h` shipped with the bundled checkers
+fn testlib_include_dir(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    app.path()
+        .resolve("testlib/include", BaseDirectory::Resource)
+        .map_err(|e| e.to_string())
+}
+
+/// An environment working in the temporary directory of `tag`,
+/// sharing the compile cache of the workspace with every other task
+fn new_judge_env(
+    db: &DatabaseRepo,
+    tag: &str,
+    env: HashMap<String, String>,
+    cancel: CancelToken,
+) -> JudgeEnv {
+    JudgeEnv::new(temp_dir(tag), env, cancel)
+        .with_compile_cache(CompileCache::new(db.get_compile_cache_folder()))
+}
+
+async fn load_judge_task(
+    app: &tauri::AppHandle,
+    db: &State<'_, DatabaseRepo>,
+    repo: &State<'_, DocumentRepo>,
+    task_tag: &str,
+    solution_id: &str,
+    profile: Option<&str>,
+    cancel: CancelToken,
+) -> Result<JudgeTask, String> {
+    let (problem_id, mut submission) = load_solution_submission(db, repo, solution_id).await?;
+    apply_compile_profile(&mut submission, profile)?;
+    let problem = db.get_problem(&problem_id).map_err(|e| e.to_string())?;
+    let env = get_default_env(app).map_err(|e| e.to_string())?;
+
+    let mode = if problem.interactive {
+        let interactor_id = problem
+            .interactor
+            .ok_or("Interactive problem has no interactor")?;
+        JudgeMode::Interactive {
+            interactor_env: new_judge_env(
+                db,
+                &format!("{}-interactor", task_tag),
+                env.clone(),
+                cancel.clone(),
+            )
+            .with_include_dir(testlib_include_dir(app)?),
+            interactor: load_checker_submission(db, repo, &interactor_id).await?,
+        }
+    } else {
+        let checker_name = problem.checker.unwrap_or("wcmp".to_string());
+        // a checker which is not bundled refers to a row of the checker table
+        let checker = if BUNDLED_CHECKER_NAME.contains(&checker_name.as_str()) {
+            CheckerProgram::Bundled(resolve_checker(app.clone(), checker_name).await?)
+        } else {
+            CheckerProgram::Custom {
+                checker_env: new_judge_env(
+                    db,
+                    &format!("{}-checker", task_tag),
+                    env.clone(),
+                    cancel.clone(),
+                )
+                .with_include_dir(testlib_include_dir(app)?),
+                checker: load_checker_submission(db, repo, &checker_name).await?,
+            }
+        };
+        JudgeMode::Standard {
+            checker,
+            io: FileIo {
+                input_file: problem.input_file,
+                output_file: problem.output_file,
+            },
+        }
+    };
+
+    let mut judge_env = new_judge_env(db, task_tag, env, cancel);
+    let capture_backtrace = {
+        let guard = db.config.read().map_err(|e| e.to_string())?;
+        guard.capture_backtrace
+    };
+    if capture_backtrace {
+        judge_env = judge_env.with_backtrace_capture();
+    }
+
+    Ok(JudgeTask {
+        judge_env,
+        submission,
+        problem_id: problem.id,
+        mode,
+        limit: RunLimit {
+            timeout_millis: problem.time_limit as u128,
+            memory_limit_kib: Some(problem.memory_limit as u64),
+            output_limit_bytes: Some(get_output_limit_bytes(db)?),
+        },
+    })
+}
+
+async fn load_testcase(
+    db: &State<'_, DatabaseRepo>,
+    repo: &State<'_, DocumentRepo>,
+    testcase: TestCase,
+) -> Result<TestcaseData, String> {
+    let input = get_string_of_doc(
+        testcase.input_document_id,
+        "content".to_string(),
+        db.clone(),
+        repo.clone(),
+    )
+    .await?;
+    let answer = get_string_of_doc(
+        testcase.answer_document_id,
+        "content".to_string(),
+        db.clone(),
+        repo.clone(),
+    )
+    .await?;
+    Ok(TestcaseData {
+        id: testcase.id,
+        input,
+        answer,
+    })
+}
+
+/// Identifies the source a run is judged with
+fn source_hash(source: &str) -> String {
+    cache::stable_hash(&[source.as_bytes()])
+}
+
+/// Keep the verdict in the history of runs, a failure is only logged since the verdict is still valid
+fn record_run(
+    db: &DatabaseRepo,
+    solution_id: &str,
+    testcase_id: &str,
+    source_hash: &str,
+    verdict: &Verdict,
+) {
+    if matches!(verdict, Verdict::Cancelled) {
+        return;
+    }
+    let usage = verdict.output().map(|output| output.usage());
+    let params = CreateRunParams {
+        solution_id,
+        testcase_id,
+        source_hash,
+        verdict: verdict.result(),
+        time_millis: usage
+            .map(|usage| usage.cpu_time_millis.unwrap_or(usage.wall_time_millis) as i32),
+        memory_kib: usage
+            .and_then(|usage| usage.peak_memory_kib)
+            .map(|memory| memory as i32),
+        checker_message: verdict.checker_message(),
+        output_file: verdict.output().map(|output| output.output_file()),
+    };
+    if let Err(e) = db.create_run(params) {
+        warn!(
+            "failed to record the run of {} on testcase {}: {}",
+            solution_id, testcase_id, e
+        );
+    }
+}
+
+/// Compile a solution in the working directory of `task_tag`, so it can be run there.
+/// Returns the verdict when the compilation fails, an unchanged solution is not compiled again.
+#[tauri::command]
+#[specta::specta]
+pub async fn compile_solution(
+    app: tauri::AppHandle,
+    db: State<'_, DatabaseRepo>,
+    repo: State<'_, DocumentRepo>,
+    tasks: State<'_, TaskRegistry>,
+    task_tag: String,
+    solution_id: String,
+    profile: Option<String>,
+) -> Result<Option<Verdict>, String> {
+    let guard = tasks.register(&task_tag);
+    let (_, mut submission) = load_solution_submission(&db, &repo, &solution_id).await?;
+    apply_compile_profile(&mut submission, profile.as_deref())?;
+    let judge_env = new_judge_env(
+        &db,
+        &task_tag,
+        get_default_env(&app).map_err(|e| e.to_string())?,
+        guard.token().clone(),
+    );
+    judge::compile(&judge_env, &submission)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+#[allow(clippy::too_many_arguments)]
+pub async fn judge_testcase(
+    app: tauri::AppHandle,
+    db: State<'_, DatabaseRepo>,
+    repo: State<'_, DocumentRepo>,
+    tasks: State<'_, TaskRegistry>,
+    task_tag: String,
+    solution_id: String,
+    testcase_id: String,
+    profile: Option<String>,
+) -> Result<Verdict, String> {
+    let guard = tasks.register(&task_tag);
+    let task = load_judge_task(
+        &app,
+        &db,
+        &repo,
+        &task_tag,
+        &solution_id,
+        profile.as_deref(),
+        guard.token().clone(),
+    )
+    .await?;
+    let testcase = db.get_testcase(&testcase_id).map_err(|e| e.to_string())?;
+    let testcase = load_testcase(&db, &repo, testcase).await?;
+
+    let app1 = app.clone();
+    let app2 = app.clone();
+    let task_tag1 = task_tag.clone();
+    let task_tag2 = task_tag.clone();
+    let verdict = judge::judge(
+        &task.judge_env,
+        &task.submission,
+        &testcase,
+        &task.mode,
+        task.limit,
+        move |e| {
+            let event = ProgramOutputEvent {
+                task_tag: task_tag1.clone(),
+                source: ProgramOutputSource::Stdout,
+                line: e.to_string(),
+            };
+            event.emit(&app1).unwrap();
+        },
+        move |e| {
+            let event = ProgramOutputEvent {
+                task_tag: task_tag2.clone(),
+                source: ProgramOutputSource::Stderr,
+                line: e.to_string(),
+            };
+            event.emit(&app2).unwrap();
+        },
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    trace!("judge {} result: {:?}", &task_tag, &verdict);
+    record_run(
+        &db,
+        &solution_id,
+        &testcase_id,
+        &source_hash(&task.submission.source),
+        &verdict,
+    );
+    Ok(verdict)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type, Event)]
+pub struct JudgeProgressEvent {
+    pub task_tag: String,
+    pub testcase_id: String,
+    pub progress: JudgeProgress,
+}
+
+/// Judge the solution against every testcase of its problem, the solution is compiled only once.
+/// Results are streamed by `JudgeProgressEvent` as soon as each testcase is finished,
+/// the command returns after all testcases are judged.
+#[tauri::command]
+#[specta::specta]
+pub async fn judge_all_testcases(
+    app: tauri::AppHandle,
+    db: State<'_, DatabaseRepo>,
+    repo: State<'_, DocumentRepo>,
+    tasks: State<'_, TaskRegistry>,
+    task_tag: String,
+    solution_id: String,
+    profile: Option<String>,
+) -> Result<(), String> {
+    let guard = tasks.register(&task_tag);
+    let task = load_judge_task(
+        &app,
+        &db,
+        &repo,
+        &task_tag,
+        &solution_id,
+        profile.as_deref(),
+        guard.token().clone(),
+    )
+    .await?;
+    let mut testcases = Vec::new();
+    for testcase in db
+        .get_testcases(&task.problem_id)
+        .map_err(|e| e.to_string())?
+    {
+        testcases.push(load_testcase(&db, &repo, testcase).await?);
+    }
+    let concurrency = {
+        let guard = db.config.read().map_err(|e| e.to_string())?;
+        guard.judge_concurrency as usize
+    };
+    trace!(
+        "judge {} testcases of {} with concurrency {}",
+        testcases.len(),
+        &task_tag,
+        concurrency
+    );
+
+    let handle = app.clone();
+    let tag = task_tag.clone();
+    let hash = source_hash(&task.submission.source);
+    judge::judge_all(
+        &task.judge_env,
+        &task.submission,
+        testcases,
+        &task.mode,
+        task.limit,
+        concurrency,
+        move |testcase_id, progress| {
+            if let JudgeProgress::Judged { verdict } = &progress {
+                record_run(
+                    &handle.state::<DatabaseRepo>(),
+                    &solution_id,
+                    testcase_id,
+                    &hash,
+                    verdict,
+                );
+            }
+            let event = JudgeProgressEvent {
+                task_tag: tag.clone(),
+                testcase_id: testcase_id.to_string(),
+                progress,
+            };
+            event.emit(&handle).unwrap();
+        },
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Recorded runs of a solution or a testcase, the newest first
+#[tauri::command]
+#[specta::specta]
+pub async fn get_runs(
+    db: State<'_, DatabaseRepo>,
+    params: GetRunsParams,
+) -> Result<Vec<Run>, String> {
+    db.get_runs(params).map_err(|e| e.to_string())
+}
+
+/// The output a recorded run kept, `None` if the solution is not run
+#[tauri::command]
+#[specta::specta]
+pub async fn get_run_output(
+    db: State<'_, DatabaseRepo>,
+    run_id: String,
+) -> Result<Option<String>, String> {
+    let Some(path) = db
+        .get_run_output_filepath(&run_id)
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(None);
+    };
+    let content = tokio::fs::read(&path).await.map_err(|e| e.to_string())?;
+    Ok(Some(String::from_utf8_lossy(&content).into_owned()))
+}
+
+#[derive(Debug, Serialize, Deserialize, Type)]
+pub struct RunStatus {
+    pub last_run: Option<Run>,
+    pub last_accepted: Option<Run>,
+    /// Hash of the current source, which is changed since a run if its `source_hash` differs
+    pub source_hash: String,
+}
+
+/// When the solution is last run and last accepted, on a testcase or on any of them
+#[tauri::command]
+#[specta::specta]
+pub async fn get_run_status(
+    db: State<'_, DatabaseRepo>,
+    repo: State<'_, DocumentRepo>,
+    solution_id: String,
+    testcase_id: Option<String>,
+) -> Result<RunStatus, String> {
+    let (_, submission) = load_solution_submission(&db, &repo, &solution_id).await?;
+    let latest = |verdict: Option<&str>| {
+        db.get_runs(GetRunsParams {
+            solution_id: Some(solution_id.clone()),
+            testcase_id: testcase_id.clone(),
+            verdict: verdict.map(str::to_string),
+            limit: Some(1),
+        })
+        .map(|runs| runs.into_iter().next())
+        .map_err(|e| e.to_string())
+    };
+    Ok(RunStatus {
+        last_run: latest(None)?,
+        last_accepted: latest(Some("AC"))?,
+        source_hash: source_hash(&submission.source),
+    })
+}
+
+/// Run the validator of the problem over the input of every testcase,
+/// returns the validation of each testcase in order.
+#[tauri::command]
+#[specta::specta]
+pub async fn validate_testcases(
+    app: tauri::AppHandle,
+    db: State<'_, DatabaseRepo>,
+    repo: State<'_, DocumentRepo>,
+    tasks: State<'_, TaskRegistry>,
+    task_tag: String,
+    problem_id: String,
+) -> Result<Vec<TestcaseValidation>, String> {
+    let guard = tasks.register(&task_tag);
+    let problem = db.get_problem(&problem_id).map_err(|e| e.to_string())?;
+    let validator_id = problem.validator.ok_or("Problem has no validator")?;
+    let validator = load_checker_submission(&db, &repo, &validator_id).await?;
+    let validator_env = new_judge_env(
+        &db,
+        &format!("{}-validator", task_tag),
+        get_default_env(&app).map_err(|e| e.to_string())?,
+        guard.token().clone(),
+    )
+    .with_include_dir(testlib_include_dir(&app)?);
+    let mut testcases = Vec::new();
+    for testcase in db.get_testcases(&problem_id).map_err(|e| e.to_string())? {
+        testcases.push(load_testcase(&db, &repo, testcase).await?);
+    }
+    judge::validate::validate_all(&validator_env, &validator, testcases)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct StressTestParams {
+    /// The solution to find a failing input for
+    pub solution_id: String,
+    /// Prints a random input, the seed is passed as its only argument
+    pub generator_id: String,
+    /// Its output is taken as the answer, usually a brute force
+    pub reference_id: String,
+    pub first_seed: u32,
+    pub max_iterations: Option<u32>,
+    pub time_budget_millis: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type, Event)]
+pub struct StressProgressEvent {
+    pub task_tag: String,
+    pub progress: StressProgress,
+}
+
+#[derive(Debug, Serialize, Deserialize, Type)]
+pub struct StressTestReport {
+    pub outcome: StressOutcome,
+    /// The testcase created from the failing input
+    pub testcase: Option<TestCase>,
+}
+
+fn save_testcase(
+    db: &DatabaseRepo,
+    repo: &DocumentRepo,
+    problem_id: &str,
+    input: &str,
+    answer: &str,
+) -> anyhow::Result<TestCase> {
+    let testcase = db.create_testcase(problem_id)?;
+    repo.manage(
+        testcase.input_document_id.clone(),
+        db.get_document_filepath(&testcase.input_document_id)?,
+    )?;
+    repo.manage(
+        testcase.answer_document_id.clone(),
+        db.get_document_filepath(&testcase.answer_document_id)?,
+    )?;
+    repo.set_string_of_doc(&testcase.input_document_id, "content", input)?;
+    repo.set_string_of_doc(&testcase.answer_document_id, "content", answer)?;
+    Ok(testcase)
+}
+
+/// Save a testcase found by the judge and refresh the testcases shown by the frontend
+fn save_new_testcase(
+    app: &tauri::AppHandle,
+    db: &DatabaseRepo,
+    repo: &DocumentRepo,
+    problem_id: &str,
+    input: &str,
+    answer: &str,
+) -> Result<TestCase, String> {
+    let testcase = save_testcase(db, repo, problem_id, input, answer).map_err(|e| e.to_string())?;
+    // invalidate query is here: src/hooks/use-testcases.tsx
+    QueryClientInvalidateEvent {
+        query_key: Some(vec!["testcases".to_string(), problem_id.to_string()]),
+    }
+    .emit(app)
+    .map_err(|e| e.to_string())?;
+    Ok(testcase)
+}
+
+/// Stress test a solution against a reference solution on inputs printed by a generator,
+/// all three are solutions of the same problem. Passed iterations are streamed by `StressProgressEvent`.
+/// The first failing input is saved as a new testcase, with the output of the reference solution as the answer.
+#[tauri::command]
+#[specta::specta]
+pub async fn stress_test(
+    app: tauri::AppHandle,
+    db: State<'_, DatabaseRepo>,
+    repo: State<'_, DocumentRepo>,
+    tasks: State<'_, TaskRegistry>,
+    task_tag: String,
+    params: StressTestParams,
+) -> Result<StressTestReport, String> {
+    let guard = tasks.register(&task_tag);
+    let task = load_judge_task(
+        &app,
+        &db,
+        &repo,
+        &task_tag,
+        &params.solution_id,
+        None,
+        guard.token().clone(),
+    )
+    .await?;
+    let env = get_default_env(&app).map_err(|e| e.to_string())?;
+    let mut programs = Vec::new();
+    for (solution_id, suffix) in [
+        (&params.generator_id, "generator"),
+        (&params.reference_id, "reference"),
+    ] {
+        let (problem_id, submission) = load_solution_submission(&db, &repo, solution_id).await?;
+        if problem_id != task.problem_id {
+            return Err(
+                "The generator and the reference must be solutions of the same problem".to_string(),
+            );
+        }
+        programs.push(StressProgram {
+            env: new_judge_env(
+                &db,
+                &format!("{}-{}", task_tag, suffix),
+                env.clone(),
+                guard.token().clone(),
+            ),
+            submission,
+        });
+    }
+    let reference = programs.pop().unwrap();
+    let generator = programs.pop().unwrap();
+    let stress_task = StressTask {
+        generator,
+        reference,
+        target: StressProgram {
+            env: task.judge_env,
+            submission: task.submission,
+        },
+        mode: task.mode,
+        limit: task.limit,
+        first_seed: params.first_seed as u64,
+        max_iterations: params.max_iterations.map(|max| max as u64),
+        time_budget_millis: params.time_budget_millis as u128,
+    };
+
+    let tag = task_tag.clone();
+    let outcome = stress::stress(&stress_task, |progress| {
+        let event = StressProgressEvent {
+            task_tag: tag.clone(),
+            progress,
+        };
+        event.emit(&app).unwrap();
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    trace!("stress test {} result: {:?}", &task_tag, &outcome);
+
+    let testcase = match &outcome {
+        StressOutcome::Mismatch { input, answer, .. } => Some(save_new_testcase(
+            &app,
+            &db,
+            &repo,
+            &task.problem_id,
+            input,
+            answer,
+        )?),
+        _ => None,
+    };
+    Ok(StressTestReport { outcome, testcase })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct MinimizeTestcaseParams {
+    /// The solution failing on the testcase
+    pub solution_id: String,
+    /// Its output is taken as the answer of every smaller input
+    pub reference_id: String,
+    pub testcase_id: String,
+    /// How many smaller inputs are tried at most
+    pub max_runs: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Type)]
+pub struct MinimizeTestcaseReport {
+    pub outcome: MinimizeOutcome,
+    /// The testcase created from the smallest failing input
+    pub testcase: Option<TestCase>,
+}
+
+/// Shrink the input of a testcase on which the solution fails by removing lines and tokens,
+/// the smallest input on which it still fails is saved as a new testcase of the same problem.
+#[tauri::command]
+#[specta::specta]
+pub async fn minimize_testcase(
+    app: tauri::AppHandle,
+    db: State<'_, DatabaseRepo>,
+    repo: State<'_, DocumentRepo>,
+    tasks: State<'_, TaskRegistry>,
+    task_tag: String,
+    params: MinimizeTestcaseParams,
+) -> Result<MinimizeTestcaseReport, String> {
+    let guard = tasks.register(&task_tag);
+    let task = load_judge_task(
+        &app,
+        &db,
+        &repo,
+        &task_tag,
+        &params.solution_id,
+        None,
+        guard.token().clone(),
+    )
+    .await?;
+    let (problem_id, reference) =
+        load_solution_submission(&db, &repo, &params.reference_id).await?;
+    if problem_id != task.problem_id {
+        return Err("The reference must be a solution of the same problem".to_string());
+    }
+    let testcase = db
+        .get_testcases(&task.problem_id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|testcase| testcase.id == params.testcase_id)
+        .ok_or("The testcase does not belong to the problem of the solution")?;
+    let input = load_testcase(&db, &repo, testcase).await?.input;
+    let minimize_task = MinimizeTask {
+        target: StressProgram {
+            env: task.judge_env,
+            submission: task.submission,
+        },
+        reference: StressProgram {
+            env: new_judge_env(
+                &db,
+                &format!("{}-reference", task_tag),
+                get_default_env(&app).map_err(|e| e.to_string())?,
+                guard.token().clone(),
+            ),
+            submission: reference,
+        },
+        mode: task.mode,
+        limit: task.limit,
+        max_runs: params.max_runs,
+    };
+
+    let outcome = minimize::minimize(&minimize_task, input)
+        .await
+        .map_err(|e| e.to_string())?;
+    trace!("minimize {} result: {:?}", &task_tag, &outcome);
+    let testcase = match &outcome {
+        MinimizeOutcome::Minimized { input, answer, .. } => Some(save_new_testcase(
+            &app,
+            &db,
+            &repo,
+            &task.problem_id,
+            input,
+            answer,
+        )?),
+        _ => None,
+    };
+    Ok(MinimizeTestcaseReport { outcome, testcase })
+}
+
+/// Run the generator recipe of a testcase, the generated input and the answer
+/// printed by its reference solution replace the contents of the testcase.
+#[tauri::command]
+#[specta::specta]
+pub async fn regenerate_testcase(
+    app: tauri::AppHandle,
+    db: State<'_, DatabaseRepo>,
+    repo: State<'_, DocumentRepo>,
+    tasks: State<'_, TaskRegistry>,
+    task_tag: String,
+    testcase_id: String,
+) -> Result<GenerateOutcome, String> {
+    let guard = tasks.register(&task_tag);
+    let testcase = db.get_testcase(&testcase_id).map_err(|e| e.to_string())?;
+    let generator_id = testcase
+        .generator
+        .as_deref()
+        .ok_or("Testcase has no generator")?;
+    let reference_id = testcase
+        .reference
+        .as_deref()
+        .ok_or("Testcase has no reference solution")?;
+    let problem = db
+        .get_problem(&testcase.problem_id)
+        .map_err(|e| e.to_string())?;
+    let env = get_default_env(&app).map_err(|e| e.to_string())?;
+    let mut programs = Vec::new();
+    for (solution_id, suffix) in [(generator_id, "generator"), (reference_id, "reference")] {
+        let (problem_id, submission) = load_solution_submission(&db, &repo, solution_id).await?;
+        if problem_id != problem.id {
+            return Err(
+                "The generator and the reference must be solutions of the same problem".to_string(),
+            );
+        }
+        programs.push(StressProgram {
+            env: new_judge_env(
+                &db,
+                &format!("{}-{}", task_tag, suffix),
+                env.clone(),
+                guard.token().clone(),
+            ),
+            submission,
+        });
+    }
+    let reference = programs.pop().unwrap();
+    let generator = programs.pop().unwrap();
+    let generate_task = GenerateTask {
+        generator,
+        generator_args: testcase.generator_args.clone().unwrap_or_default(),
+        reference,
+        io: FileIo {
+            input_file: problem.input_file,
+            output_file: problem.output_file,
+        },
+        limit: RunLimit {
+            timeout_millis: problem.time_limit as u128,
+            memory_limit_kib: Some(problem.memory_limit as u64),
+            output_limit_bytes: Some(get_output_limit_bytes(&db)?),
+        },
+    };
+
+    let outcome = generate::generate(&generate_task, &testcase.id)
+        .await
+        .map_err(|e| e.to_string())?;
+    trace!("regenerate {} result: {:?}", &task_tag, &outcome);
+    if let GenerateOutcome::Generated { input, answer } = &outcome {
+        for (document_id, content) in [
+            (&testcase.input_document_id, input),
+            (&testcase.answer_document_id, answer),
+        ] {
+            if !repo.has(document_id) {
+                repo.manage(
+                    document_id.clone(),
+                    db.get_document_filepath(document_id)
+                        .map_err(|e| e.to_string())?,
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            repo.set_string_of_doc(document_id, "content", content)
+                .map_err(|e| e.to_string())?;
+        }
+        // invalidate query is here: src/hooks/use-testcases.tsx
+        QueryClientInvalidateEvent {
+            query_key: Some(vec!["testcases".to_string(), problem.id.clone()]),
+        }
+        .emit(&app)
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(outcome)
+}
@@ -8,6 +8,7 @@ use tauri::{Manager, Runtime, State};
 use tauri_specta::Event;
 
 pub mod database;
+pub mod judge;
 pub mod runner;
 
 #[derive(Debug, Serialize, Deserialize, Event, Clone, Type)]
@@ -110,7 +111,11 @@ pub async fn get_default_create_problem_params(
         statement,
         checker: Some("wcmp".to_string()),
         time_limit: 3000,
-        memory_limit: 1024,
+        // 256 MiB, in KiB
+        memory_limit: 262144,
+        interactive: false,
+        input_file: None,
+        output_file: None,
         initial_solution: Some(get_default_create_solution_params(app, name.clone()).await?),
     };
 
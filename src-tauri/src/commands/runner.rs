@@ -2,6 +2,7 @@ use std::{
     collections::HashMap,
     hash::{DefaultHasher, Hash, Hasher},
     path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
 };
 
 use log::trace;
@@ -12,15 +13,31 @@ use tauri_plugin_shell::ShellExt;
 use tauri_specta::Event;
 use tokio::sync::RwLock;
 
-use crate::runner::{
-    cmd::parse_command_with_env,
-    command_flag_create_new_console, get_bundled_checker_names,
-    lang_server::{IOMethod, LangServerProcess, LangServerWriter},
-    run::{launch_program, launch_program_without_input, ProgramOutput, ProgramSimpleOutput},
-    temp_dir,
+use crate::{
+    config::ProgramConfigRepo,
+    database::DatabaseRepo,
+    runner::{
+        cancel::CancelToken,
+        cmd::parse_command_with_env,
+        command_flag_create_new_console, get_bundled_checker_names,
+        lang_server::{IOMethod, LangServerProcess, LangServerWriter},
+        pauser::PAUSER_ARG,
+        run::{
+            launch_program, launch_program_without_input, ProgramOutput, ProgramSimpleOutput,
+            RunLimit,
+        },
+        temp_dir,
+    },
 };
 
 pub static ENV_KEY_BUNDLED_LSP: &str = "BUNDLED_LSP";
+
+/// The output limit configured in the workspace, in bytes
+pub fn get_output_limit_bytes(db: &DatabaseRepo) -> Result<u64, String> {
+    let guard = db.config.read().map_err(|e| e.to_string())?;
+    Ok(guard.output_limit_mib as u64 * 1024 * 1024)
+}
+
 pub fn get_default_env(app: &tauri::AppHandle) -> anyhow::Result<HashMap<String, String>> {
     let path_resolver = app.path();
     let mut env = HashMap::new();
@@ -168,6 +185,92 @@ pub async fn send_message_to_language_server(
     Ok(())
 }
 
+/// Cancel tokens of the running tasks, keyed by task tag.
+/// A tag may be shared by several tasks, e.g. a compilation and a run, all of them are cancelled together.
+#[derive(Default)]
+pub struct TaskRegistry {
+    // std mutex, so that the guard is able to deregister itself in drop
+    tasks: std::sync::Mutex<HashMap<String, Vec<(u64, CancelToken)>>>,
+    next_id: AtomicU64,
+}
+
+impl TaskRegistry {
+    /// Register a running task, it is deregistered once the returned guard is dropped
+    pub fn register(&self, task_tag: &str) -> TaskGuard<'_> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let token = CancelToken::new();
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks
+            .entry(task_tag.to_string())
+            .or_default()
+            .push((id, token.clone()));
+        TaskGuard {
+            registry: self,
+            task_tag: task_tag.to_string(),
+            id,
+            token,
+        }
+    }
+
+    /// Cancel every running task with the tag, returns false if there is none
+    pub fn cancel(&self, task_tag: &str) -> bool {
+        let tasks = self.tasks.lock().unwrap();
+        match tasks.get(task_tag) {
+            Some(tokens) => {
+                for (_, token) in tokens {
+                    token.cancel();
+                }
+                !tokens.is_empty()
+            }
+            None => false,
+        }
+    }
+
+    pub fn cancel_all(&self) {
+        let tasks = self.tasks.lock().unwrap();
+        for (_, token) in tasks.values().flatten() {
+            token.cancel();
+        }
+    }
+}
+
+pub struct TaskGuard<'a> {
+    registry: &'a TaskRegistry,
+    task_tag: String,
+    id: u64,
+    token: CancelToken,
+}
+
+impl TaskGuard<'_> {
+    pub fn token(&self) -> &CancelToken {
+        &self.token
+    }
+}
+
+impl Drop for TaskGuard<'_> {
+    fn drop(&mut self) {
+        let mut tasks = self.registry.tasks.lock().unwrap();
+        if let Some(tokens) = tasks.get_mut(&self.task_tag) {
+            tokens.retain(|(id, _)| *id != self.id);
+            if tokens.is_empty() {
+                tasks.remove(&self.task_tag);
+            }
+        }
+    }
+}
+
+/// Kill the programs started with the task tag, the pending commands of the task
+/// resolve with a cancelled outcome. Returns false if no such task is running.
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_task(
+    tasks: tauri::State<'_, TaskRegistry>,
+    task_tag: String,
+) -> Result<bool, String> {
+    trace!("cancel task {}", &task_tag);
+    Ok(tasks.cancel(&task_tag))
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
 pub enum ProgramOutputSource {
     Stdout,
@@ -176,9 +279,9 @@ pub enum ProgramOutputSource {
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type, Event)]
 pub struct ProgramOutputEvent {
-    task_tag: String,
-    source: ProgramOutputSource,
-    line: String,
+    pub task_tag: String,
+    pub source: ProgramOutputSource,
+    pub line: String,
 }
 
 #[tauri::command]
@@ -212,11 +315,14 @@ pub async fn write_file_to_task_tag(
 #[specta::specta]
 pub async fn execute_program_callback(
     app: tauri::AppHandle,
+    db: tauri::State<'_, DatabaseRepo>,
+    tasks: tauri::State<'_, TaskRegistry>,
     task_tag: String,
     commands: String,
     env: HashMap<String, String>,
     input_filename: PathBuf,
     timeout_millis: u32,
+    memory_limit_kib: Option<u32>,
 ) -> Result<ProgramOutput, String> {
     let def_env = get_default_env(&app).map_err(|e| e.to_string())?;
     let mut env: HashMap<String, String> = env.into_iter().chain(def_env.into_iter()).collect();
@@ -231,6 +337,7 @@ pub async fn execute_program_callback(
     let input_hash = hasher.finish();
     let output_file = temp_dir.join(format!("output-{:x}.txt", input_hash));
 
+    let task = tasks.register(&task_tag);
     let app1 = app.clone();
     let app2 = app.clone();
     let task_tag1 = task_tag;
@@ -240,7 +347,12 @@ pub async fn execute_program_callback(
         cmd,
         input_filename,
         output_file,
-        timeout_millis as u128,
+        RunLimit {
+            timeout_millis: timeout_millis as u128,
+            memory_limit_kib: memory_limit_kib.map(|x| x as u64),
+            output_limit_bytes: Some(get_output_limit_bytes(&db)?),
+        },
+        task.token(),
         move |e| {
             let event = ProgramOutputEvent {
                 task_tag: task_tag1.clone(),
@@ -268,6 +380,7 @@ pub async fn execute_program_callback(
 #[specta::specta]
 pub async fn execute_program(
     app: tauri::AppHandle,
+    tasks: tauri::State<'_, TaskRegistry>,
     task_tag: String,
     commands: String,
     env: HashMap<String, String>,
@@ -282,17 +395,20 @@ pub async fn execute_program(
     cmd.current_dir(&temp_dir);
 
     log::trace!("launch program: {:?}", &cmd);
-    let output = launch_program_without_input(cmd, timeout_millis as u128)
+    let task = tasks.register(&task_tag);
+    let output = launch_program_without_input(cmd, timeout_millis as u128, task.token())
         .await
         .map_err(|e| e.to_string())?;
 
     Ok(output)
 }
 
+/// Run a program in a terminal, which is kept open after the program exits
 #[tauri::command]
 #[specta::specta]
 pub async fn execute_program_detached(
     app: tauri::AppHandle,
+    cfg: tauri::State<'_, ProgramConfigRepo>,
     task_tag: String,
     command: String,
     env: HashMap<String, String>,
@@ -305,28 +421,95 @@ pub async fn execute_program_detached(
     env.insert("CWD".to_string(), temp_dir.display().to_string());
 
     let origin_cmd = parse_command_with_env(&command, &env)?;
-    let cmd: std::process::Command = app
-        .shell()
-        .sidecar("consolepauser")
-        .or_else(|e| Err(format!("console pauser not found in installation: {e}")))?
-        .into();
-
-    // DO NOT USE UNC PATH
-    // consolepauser can not work noramlly with UNC path, which is so strange but keep it in mind
-    let mut cmd = std::process::Command::new(dunce::canonicalize(cmd.get_program()).unwrap());
-    cmd.current_dir(&temp_dir)
-        .arg("1")
-        // .arg(PathBuf::from(origin_cmd.get_program()).file_name().unwrap())
+    let terminal_command = cfg
+        .read()
+        .map_err(|e| e.to_string())?
+        .terminal_command
+        .clone();
+
+    // the pauser reports the exit code and waits for a key before the terminal closes
+    let mut pauser = if cfg!(target_os = "windows") {
+        let cmd: std::process::Command = app
+            .shell()
+            .sidecar("consolepauser")
+            .or_else(|e| Err(format!("console pauser not found in installation: {e}")))?
+            .into();
+        // DO NOT USE UNC PATH
+        // consolepauser can not work noramlly with UNC path, which is so strange but keep it in mind
+        let mut cmd = std::process::Command::new(dunce::canonicalize(cmd.get_program()).unwrap());
+        cmd.arg("1");
+        cmd
+    } else {
+        let mut cmd =
+            std::process::Command::new(std::env::current_exe().map_err(|e| e.to_string())?);
+        cmd.arg(PAUSER_ARG);
+        cmd
+    };
+    pauser
         .arg(origin_cmd.get_program())
         .args(origin_cmd.get_args());
 
-    // TODO: not work on LINUX, need futhuer investgivate
-    command_flag_create_new_console(&mut cmd);
+    let mut cmd = if terminal_command.trim().is_empty() {
+        if !cfg!(target_os = "windows") {
+            return Err(
+                "No terminal is configured, set the terminal command in preferences".into(),
+            );
+        }
+        command_flag_create_new_console(&mut pauser);
+        pauser
+    } else {
+        let mut cmd = parse_command_with_env(&terminal_command, &env)?;
+        cmd.arg(pauser.get_program()).args(pauser.get_args());
+        cmd
+    };
     cmd.current_dir(&temp_dir);
 
     trace!("launch program detached cmd: {:?}", &cmd);
-    cmd.spawn().map_err(|e| e.to_string())?;
-    // trace!("output: {:?}", cmd.output());
+    let mut child = cmd.spawn().map_err(|e| e.to_string())?;
+    // reap the terminal once it is closed
+    std::thread::spawn(move || child.wait());
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_tasks_by_tag() {
+        let registry = TaskRegistry::default();
+        let compile = registry.register("a");
+        let run = registry.register("a");
+        let other = registry.register("b");
+        assert!(registry.cancel("a"));
+        assert!(compile.token().is_cancelled());
+        assert!(run.token().is_cancelled());
+        assert!(!other.token().is_cancelled());
+        assert!(!registry.cancel("c"));
+    }
+
+    #[test]
+    fn test_deregister_on_drop() {
+        let registry = TaskRegistry::default();
+        let first = registry.register("a");
+        let second = registry.register("a");
+        drop(first);
+        // the other task with the same tag is still registered
+        assert!(registry.cancel("a"));
+        assert!(second.token().is_cancelled());
+        drop(second);
+        assert!(!registry.cancel("a"));
+        assert!(registry.tasks.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_cancel_all() {
+        let registry = TaskRegistry::default();
+        let a = registry.register("a");
+        let b = registry.register("b");
+        registry.cancel_all();
+        assert!(a.token().is_cancelled());
+        assert!(b.token().is_cancelled());
+    }
+}
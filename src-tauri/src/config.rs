@@ -23,6 +23,9 @@ pub struct ProgramConfig {
     pub competitive_companion_enabled: bool,
     pub workspace_history: Vec<PathBuf>,
     pub keymap: Keymap,
+    /// Opens a terminal running the program appended to it, like `x-terminal-emulator -e`.
+    /// Empty opens a new console on Windows.
+    pub terminal_command: String,
 }
 
 impl From<ProgramConfigLocalDeserialized> for ProgramConfig {
@@ -35,6 +38,7 @@ impl From<ProgramConfigLocalDeserialized> for ProgramConfig {
             competitive_companion_enabled: value.competitive_companion_enabled,
             workspace_history: value.workspace_history,
             keymap: value.keymap,
+            terminal_command: value.terminal_command,
         }
     }
 }
@@ -60,6 +64,9 @@ pub struct ProgramConfigLocalDeserialized {
 
     #[serde(default = "ProgramConfigLocalDeserialized::default_keymap")]
     pub keymap: Keymap,
+
+    #[serde(default = "ProgramConfigLocalDeserialized::default_terminal_command")]
+    pub terminal_command: String,
 }
 
 impl ProgramConfigLocalDeserialized {
@@ -84,6 +91,13 @@ impl ProgramConfigLocalDeserialized {
     fn default_keymap() -> Keymap {
         Keymap::Default
     }
+    fn default_terminal_command() -> String {
+        if cfg!(target_os = "windows") {
+            String::new()
+        } else {
+            "x-terminal-emulator -e".to_string()
+        }
+    }
 }
 
 impl Default for ProgramConfigLocalDeserialized {
@@ -96,6 +110,7 @@ impl Default for ProgramConfigLocalDeserialized {
             competitive_companion_enabled: Self::default_competitive_companion_enabled(),
             workspace_history: Self::default_workspace_history(),
             keymap: Self::default_keymap(),
+            terminal_command: Self::default_terminal_command(),
         }
     }
 }
@@ -95,17 +95,26 @@ pub async fn handle_competitive_companion_message(
     let db = app.state::<DatabaseRepo>();
     let doc_repo = app.state::<DocumentRepo>();
     let message: CompetitiveCompanionMessage = serde_json::from_str(message)?;
-    let result = db.create_problem(
-        get_default_create_problem_params(
-            app.clone(),
-            message.name.clone(),
-            Some(message.group.clone()),
-            Some(message.url.clone()),
-            None,
-        )
-        .await
-        .map_err(|e| anyhow::anyhow!(e))?,
-    )?;
+    let mut params = get_default_create_problem_params(
+        app.clone(),
+        message.name.clone(),
+        Some(message.group.clone()),
+        Some(message.url.clone()),
+        None,
+    )
+    .await
+    .map_err(|e| anyhow::anyhow!(e))?;
+    params.time_limit = message.time_limit as i32;
+    // competitive companion reports memory limit in MB, but we store it in KiB
+    params.memory_limit = (message.memory_limit * 1024) as i32;
+    params.interactive = message.interactive.unwrap_or(false);
+    if let CompetitiveCompanionInputType::File { file_name } = &message.input {
+        params.input_file = Some(file_name.clone());
+    }
+    if let CompetitiveCompanionOutputType::File { file_name } = &message.output {
+        params.output_file = Some(file_name.clone());
+    }
+    let result = db.create_problem(params)?;
 
     let id = result.problem.id;
     for test in message.tests {
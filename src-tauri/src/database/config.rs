@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     path::{self, PathBuf},
 };
 
@@ -14,17 +14,68 @@ pub enum LanguageServerProtocolConnectionType {
     WebSocket,
 }
 
+/// A command building the solution after `cmd_compile`, run in the working directory
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct BuildStep {
+    pub name: String,
+    pub command: String,
+    /// The timeout of the compilation is used if not set
+    pub timeout_millis: Option<u32>,
+    /// Set for the command, and substituted for `%NAME` in it
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Files relative to the working directory which the step must produce
+    #[serde(default)]
+    pub artifacts: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
 
 pub struct AdvLanguageItem {
     pub base: LanguageBase,
     pub cmd_compile: String,
+    /// Compile the solution with debug info, used to capture the backtrace of a runtime error
+    #[serde(default)]
+    pub cmd_compile_debug: Option<String>,
+    /// Compile commands selectable by name for a run instead of `cmd_compile`
+    #[serde(default)]
+    pub compile_profiles: HashMap<String, String>,
+    /// Run in order after `cmd_compile`, the build stops at the first failed step
+    #[serde(default)]
+    pub build_steps: Vec<BuildStep>,
     pub cmd_before_run: Option<String>,
     pub cmd_after_run: Option<String>,
     pub cmd_run: String,
     pub lsp: Option<String>,
     pub lsp_connect: Option<LanguageServerProtocolConnectionType>,
     pub initial_solution_content: Option<String>,
+    /// Run the solution in a sandbox, only supported on Linux
+    #[serde(default)]
+    pub sandbox: bool,
+    /// The time limit of a problem is multiplied by it for solutions in this language
+    #[serde(default = "AdvLanguageItem::default_time_multiplier")]
+    pub time_multiplier: f64,
+    /// Added to the time limit after it is multiplied
+    #[serde(default)]
+    pub time_extra_millis: u32,
+}
+
+impl AdvLanguageItem {
+    fn default_time_multiplier() -> f64 {
+        1.0
+    }
+
+    /// The time limit for solutions in this language given the time limit of the problem
+    pub fn effective_time_limit_millis(&self, time_limit_millis: u128) -> u128 {
+        let multiplier = if self.time_multiplier.is_finite() && self.time_multiplier > 0.0 {
+            self.time_multiplier
+        } else {
+            1.0
+        };
+        // the cast saturates, so a huge limit stays huge instead of wrapping around
+        ((time_limit_millis as f64 * multiplier).round() as u128)
+            .saturating_add(self.time_extra_millis as u128)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
@@ -36,6 +87,12 @@ pub struct WorkspaceConfig {
     pub default_language: Option<String>,
     pub duplicate_save: bool,
     pub duplicate_save_location: Option<PathBuf>,
+    /// How many testcases are judged at the same time when running all of them
+    pub judge_concurrency: u32,
+    /// A program is killed once it writes more than this to stdout
+    pub output_limit_mib: u32,
+    /// Re-run a crashing testcase under gdb to capture the backtrace of the runtime error
+    pub capture_backtrace: bool,
 }
 
 impl From<WorkspaceLocalDeserialized> for WorkspaceConfig {
@@ -47,6 +104,9 @@ impl From<WorkspaceLocalDeserialized> for WorkspaceConfig {
             default_language: value.default_language,
             duplicate_save: value.duplicate_save,
             duplicate_save_location: value.duplicate_save_location,
+            judge_concurrency: value.judge_concurrency,
+            output_limit_mib: value.output_limit_mib,
+            capture_backtrace: value.capture_backtrace,
         }
     }
 }
@@ -66,6 +126,12 @@ pub struct WorkspaceLocalDeserialized {
     pub duplicate_save: bool,
     #[serde(default = "WorkspaceLocalDeserialized::default_duplicate_save_location")]
     pub duplicate_save_location: Option<PathBuf>,
+    #[serde(default = "WorkspaceLocalDeserialized::default_judge_concurrency")]
+    pub judge_concurrency: u32,
+    #[serde(default = "WorkspaceLocalDeserialized::default_output_limit_mib")]
+    pub output_limit_mib: u32,
+    #[serde(default)]
+    pub capture_backtrace: bool,
 }
 impl WorkspaceLocalDeserialized {
     fn default_font_size() -> u32 {
@@ -81,6 +147,13 @@ impl WorkspaceLocalDeserialized {
             AdvLanguageItem {
                 base: LanguageBase::Cpp,
                 cmd_compile: "g++ -std=c++17 -o main %SRC".to_string(),
+                cmd_compile_debug: Some("g++ -std=c++17 -g -O0 -o main %SRC".to_string()),
+                compile_profiles: HashMap::from([(
+                    "sanitize".to_string(),
+                    "g++ -std=c++17 -g -fsanitize=address,undefined -fno-sanitize-recover=undefined -o main %SRC"
+                        .to_string(),
+                )]),
+                build_steps: vec![],
                 cmd_before_run: None,
                 cmd_after_run: None,
                 cmd_run: format!(
@@ -106,6 +179,9 @@ impl WorkspaceLocalDeserialized {
                 initial_solution_content: Some(
                     "#include<iostream>\nint main(){\n\treturn 0;\n}".to_string(),
                 ),
+                sandbox: false,
+                time_multiplier: 1.0,
+                time_extra_millis: 0,
             },
         );
         language
@@ -116,6 +192,16 @@ impl WorkspaceLocalDeserialized {
     fn default_duplicate_save_location() -> Option<PathBuf> {
         None
     }
+    fn default_judge_concurrency() -> u32 {
+        // running too many solutions at once makes the measured time unreliable
+        std::thread::available_parallelism()
+            .map(|n| n.get() as u32)
+            .unwrap_or(1)
+            .min(4)
+    }
+    fn default_output_limit_mib() -> u32 {
+        64
+    }
 }
 
 impl Default for WorkspaceLocalDeserialized {
@@ -127,6 +213,64 @@ impl Default for WorkspaceLocalDeserialized {
             default_language: None,
             duplicate_save: Self::default_duplicate_save(),
             duplicate_save_location: Self::default_duplicate_save_location(),
+            judge_concurrency: Self::default_judge_concurrency(),
+            output_limit_mib: Self::default_output_limit_mib(),
+            capture_backtrace: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cpp(time_multiplier: f64, time_extra_millis: u32) -> AdvLanguageItem {
+        AdvLanguageItem {
+            time_multiplier,
+            time_extra_millis,
+            ..WorkspaceLocalDeserialized::default_language()["cpp 17"].clone()
+        }
+    }
+
+    #[test]
+    fn test_default_time_limit() {
+        for language in WorkspaceLocalDeserialized::default_language().values() {
+            assert_eq!(language.effective_time_limit_millis(1000), 1000);
+        }
+    }
+
+    #[test]
+    fn test_scaled_time_limit() {
+        assert_eq!(cpp(2.0, 0).effective_time_limit_millis(1000), 2000);
+        assert_eq!(cpp(1.0, 500).effective_time_limit_millis(1000), 1500);
+        assert_eq!(cpp(1.5, 200).effective_time_limit_millis(1000), 1700);
+        assert_eq!(cpp(1.25, 0).effective_time_limit_millis(3), 4);
+        assert_eq!(cpp(0.5, 0).effective_time_limit_millis(3), 2);
+    }
+
+    #[test]
+    fn test_invalid_time_multiplier() {
+        for multiplier in [0.0, -2.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(cpp(multiplier, 0).effective_time_limit_millis(1000), 1000);
         }
     }
+
+    #[test]
+    fn test_time_limit_overflow() {
+        assert_eq!(
+            cpp(2.0, u32::MAX).effective_time_limit_millis(u128::MAX),
+            u128::MAX
+        );
+    }
+
+    #[test]
+    fn test_time_multiplier_default() {
+        let mut value = serde_json::to_value(cpp(2.0, 100)).unwrap();
+        let item = value.as_object_mut().unwrap();
+        item.remove("time_multiplier");
+        item.remove("time_extra_millis");
+        let language: AdvLanguageItem = serde_json::from_value(value).unwrap();
+        assert_eq!(language.time_multiplier, 1.0);
+        assert_eq!(language.time_extra_millis, 0);
+    }
 }
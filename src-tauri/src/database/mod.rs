@@ -1,23 +1,23 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
 use crate::database::config::{AdvLanguageItem, WorkspaceConfig};
-use crate::schema::{documents, problems, solutions, test_cases};
+use crate::schema::{documents, problems, runs, solutions, test_cases};
 use anyhow::Result;
 use diesel::prelude::*;
 use diesel::{
     r2d2::{ConnectionManager, Pool},
     SqliteConnection,
 };
-use log::trace;
+use log::{trace, warn};
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use uuid::Uuid;
 
 use crate::model::{
-    Checker, Document, Problem, ProblemChangeset, ProblemRow, Solution, SolutionChangeset,
-    SolutionRow, TestCase,
+    Checker, Document, Problem, ProblemChangeset, ProblemRow, Run, Solution, SolutionChangeset,
+    SolutionRow, TestCase, TestCaseChangeset,
 };
 
 pub mod competitive_companion;
@@ -72,6 +72,9 @@ pub struct CreateProblemParams {
     pub checker: Option<String>,
     pub time_limit: i32,
     pub memory_limit: i32,
+    pub interactive: bool,
+    pub input_file: Option<String>,
+    pub output_file: Option<String>,
     pub initial_solution: Option<CreateSolutionParams>,
 }
 
@@ -111,6 +114,28 @@ pub struct CreateCheckerResult {
     pub checker: Checker,
 }
 
+#[derive(Debug, Serialize, Deserialize, Type)]
+pub struct GetRunsParams {
+    pub solution_id: Option<String>,
+    pub testcase_id: Option<String>,
+    /// Only the runs with this result, like `AC`
+    pub verdict: Option<String>,
+    pub limit: Option<i32>,
+}
+
+#[derive(Debug)]
+pub struct CreateRunParams<'a> {
+    pub solution_id: &'a str,
+    pub testcase_id: &'a str,
+    pub source_hash: &'a str,
+    pub verdict: &'a str,
+    pub time_millis: Option<i32>,
+    pub memory_kib: Option<i32>,
+    pub checker_message: Option<&'a str>,
+    /// Copied into the runs folder, so it is kept after the working directory is cleaned
+    pub output_file: Option<&'a Path>,
+}
+
 impl DatabaseRepo {
     pub fn new(
         pool: Pool<ConnectionManager<SqliteConnection>>,
@@ -125,6 +150,14 @@ impl DatabaseRepo {
             config: Arc::new(RwLock::new(config)),
         }
     }
+    /// Compiled programs are kept here across restarts
+    pub fn get_compile_cache_folder(&self) -> PathBuf {
+        self.base_folder.join("compile-cache")
+    }
+    /// Outputs of the recorded runs are kept here
+    pub fn get_run_output_folder(&self) -> PathBuf {
+        self.base_folder.join("runs")
+    }
     pub fn save_config(&self, filename: &str) -> Result<()> {
         let guard = self.config.read().unwrap();
         let content = toml::to_string_pretty(&*guard)?;
@@ -192,6 +225,9 @@ impl DatabaseRepo {
                 problems::url.eq(&params.url),
                 problems::time_limit.eq(params.time_limit),
                 problems::memory_limit.eq(params.memory_limit),
+                problems::interactive.eq(params.interactive),
+                problems::input_file.eq(&params.input_file),
+                problems::output_file.eq(&params.output_file),
                 problems::group.eq(&group),
                 problems::statement.eq(&params.statement),
                 problems::checker.eq(&params.checker),
@@ -222,6 +258,11 @@ impl DatabaseRepo {
                 modified_datetime: now,
                 time_limit: params.time_limit,
                 memory_limit: params.memory_limit,
+                interactive: params.interactive,
+                interactor: None,
+                input_file: params.input_file,
+                output_file: params.output_file,
+                validator: None,
                 solutions,
             };
 
@@ -376,10 +417,66 @@ impl DatabaseRepo {
         })
     }
 
+    pub fn get_checker(&self, checker_id: &str) -> Result<Checker> {
+        let mut conn = self.pool.get().map_err(|e| anyhow::anyhow!("{}", e))?;
+        let (id, name, language, description, document_id) = crate::schema::checker::table
+            .filter(crate::schema::checker::id.eq(checker_id))
+            .select((
+                crate::schema::checker::id,
+                crate::schema::checker::name,
+                crate::schema::checker::language,
+                crate::schema::checker::description,
+                crate::schema::checker::document_id,
+            ))
+            .first::<(String, String, String, Option<String>, String)>(&mut conn)?;
+        let document = self.get_document(&document_id)?;
+        Ok(Checker {
+            id,
+            name,
+            language,
+            description,
+            document_id,
+            document,
+        })
+    }
+
+    pub fn get_checkers(&self) -> Result<Vec<Checker>> {
+        let mut conn = self.pool.get().map_err(|e| anyhow::anyhow!("{}", e))?;
+        let ids = crate::schema::checker::table
+            .select(crate::schema::checker::id)
+            .order(crate::schema::checker::name.asc())
+            .load::<String>(&mut conn)?;
+        ids.iter().map(|id| self.get_checker(id)).collect()
+    }
+
     pub fn delete_problem(&self, problem_id: &str) -> Result<()> {
         let mut conn = self.pool.get().map_err(|e| anyhow::anyhow!("{}", e))?;
 
-        diesel::delete(problems::table.filter(problems::id.eq(problem_id))).execute(&mut conn)?;
+        let output_files = conn.transaction(|txn| {
+            let problem_runs = runs::table
+                .filter(
+                    runs::solution_id.eq_any(
+                        solutions::table
+                            .filter(solutions::problem_id.eq(problem_id))
+                            .select(solutions::id),
+                    ),
+                )
+                .or_filter(
+                    runs::testcase_id.eq_any(
+                        test_cases::table
+                            .filter(test_cases::problem_id.eq(problem_id))
+                            .select(test_cases::id),
+                    ),
+                );
+            let output_files = problem_runs
+                .select(runs::output_file)
+                .load::<Option<String>>(txn)?;
+            diesel::delete(problem_runs).execute(txn)?;
+            diesel::delete(problems::table.filter(problems::id.eq(problem_id)))
+                .execute(txn)
+                .map(|_| output_files)
+        })?;
+        self.remove_run_outputs(output_files);
         Ok(())
     }
 
@@ -396,8 +493,17 @@ impl DatabaseRepo {
             .filter(solutions::id.eq(solution_id))
             .select(solutions::problem_id)
             .first::<String>(&mut conn)?;
-        diesel::delete(solutions::table.filter(solutions::id.eq(solution_id)))
-            .execute(&mut conn)?;
+        let output_files = conn.transaction(|txn| {
+            let output_files = runs::table
+                .filter(runs::solution_id.eq(solution_id))
+                .select(runs::output_file)
+                .load::<Option<String>>(txn)?;
+            diesel::delete(runs::table.filter(runs::solution_id.eq(solution_id))).execute(txn)?;
+            diesel::delete(solutions::table.filter(solutions::id.eq(solution_id)))
+                .execute(txn)
+                .map(|_| output_files)
+        })?;
+        self.remove_run_outputs(output_files);
         Ok(problem_id)
     }
 
@@ -422,6 +528,11 @@ impl DatabaseRepo {
             statement: problem_row.statement,
             time_limit: problem_row.time_limit,
             memory_limit: problem_row.memory_limit,
+            interactive: problem_row.interactive,
+            interactor: problem_row.interactor,
+            input_file: problem_row.input_file,
+            output_file: problem_row.output_file,
+            validator: problem_row.validator,
             checker: problem_row.checker,
             create_datetime: problem_row.create_datetime,
             modified_datetime: problem_row.modified_datetime,
@@ -533,6 +644,11 @@ impl DatabaseRepo {
                 checker: row.checker.clone(),
                 time_limit: row.time_limit,
                 memory_limit: row.memory_limit,
+                interactive: row.interactive,
+                interactor: row.interactor.clone(),
+                input_file: row.input_file.clone(),
+                output_file: row.output_file.clone(),
+                validator: row.validator.clone(),
                 create_datetime: row.create_datetime,
                 modified_datetime: row.modified_datetime,
                 solutions: problem_solutions,
@@ -624,6 +740,14 @@ impl DatabaseRepo {
             .load::<TestCase>(&mut conn)?;
         Ok(testcases)
     }
+    pub fn get_testcase(&self, testcase_id: &str) -> Result<TestCase> {
+        let mut conn = self.pool.get().map_err(|e| anyhow::anyhow!("{}", e))?;
+        let testcase = test_cases::table
+            .filter(test_cases::id.eq(testcase_id))
+            .select(TestCase::as_select())
+            .first::<TestCase>(&mut conn)?;
+        Ok(testcase)
+    }
     pub fn create_testcase(&self, problem_id: &str) -> Result<TestCase> {
         let mut conn = self.pool.get().map_err(|e| anyhow::anyhow!("{}", e))?;
         let input_document_id = Uuid::new_v4().to_string();
@@ -647,6 +771,9 @@ impl DatabaseRepo {
             problem_id: problem_id.to_string(),
             input_document_id,
             answer_document_id,
+            generator: None,
+            generator_args: None,
+            reference: None,
         };
         conn.transaction(|txn| {
             diesel::insert_into(documents::table)
@@ -662,12 +789,101 @@ impl DatabaseRepo {
 
         Ok(testcase)
     }
-    pub fn delete_testcase(&self, testcase_id: &str) -> Result<()> {
+    pub fn update_testcase(&self, testcase_id: &str, params: TestCaseChangeset) -> Result<()> {
         let mut conn = self.pool.get().map_err(|e| anyhow::anyhow!("{}", e))?;
-        diesel::delete(test_cases::table.filter(test_cases::id.eq(testcase_id)))
+
+        diesel::update(test_cases::table.filter(test_cases::id.eq(testcase_id)))
+            .set(&params)
             .execute(&mut conn)?;
         Ok(())
     }
+    pub fn delete_testcase(&self, testcase_id: &str) -> Result<()> {
+        let mut conn = self.pool.get().map_err(|e| anyhow::anyhow!("{}", e))?;
+        let output_files = conn.transaction(|txn| {
+            let output_files = runs::table
+                .filter(runs::testcase_id.eq(testcase_id))
+                .select(runs::output_file)
+                .load::<Option<String>>(txn)?;
+            diesel::delete(runs::table.filter(runs::testcase_id.eq(testcase_id))).execute(txn)?;
+            diesel::delete(test_cases::table.filter(test_cases::id.eq(testcase_id)))
+                .execute(txn)
+                .map(|_| output_files)
+        })?;
+        self.remove_run_outputs(output_files);
+        Ok(())
+    }
+    /// Remove the outputs kept in the runs folder for runs already deleted from the database.
+    /// Foreign keys are not enforced by SQLite here, so the rows are never cascaded
+    /// and have to be deleted along with their solution or testcase
+    fn remove_run_outputs(&self, output_files: Vec<Option<String>>) {
+        let folder = self.get_run_output_folder();
+        for filename in output_files.into_iter().flatten() {
+            let path = folder.join(filename);
+            if let Err(e) = std::fs::remove_file(&path) {
+                warn!("failed to remove run output {:?}: {}", &path, e);
+            }
+        }
+    }
+    pub fn create_run(&self, params: CreateRunParams) -> Result<Run> {
+        let mut conn = self.pool.get().map_err(|e| anyhow::anyhow!("{}", e))?;
+        let run_id = Uuid::new_v4().to_string();
+        let output_file = match params.output_file {
+            Some(path) if path.exists() => {
+                let filename = format!("{}.out", &run_id);
+                let folder = self.get_run_output_folder();
+                std::fs::create_dir_all(&folder)?;
+                std::fs::copy(path, folder.join(&filename))?;
+                Some(filename)
+            }
+            _ => None,
+        };
+        let run = Run {
+            id: run_id,
+            solution_id: params.solution_id.to_string(),
+            testcase_id: params.testcase_id.to_string(),
+            source_hash: params.source_hash.to_string(),
+            verdict: params.verdict.to_string(),
+            time_millis: params.time_millis,
+            memory_kib: params.memory_kib,
+            checker_message: params.checker_message.map(str::to_string),
+            output_file,
+            create_datetime: chrono::Local::now().naive_local(),
+        };
+        diesel::insert_into(runs::table)
+            .values(&run)
+            .execute(&mut conn)?;
+        Ok(run)
+    }
+    /// Runs matching every given filter, the newest first
+    pub fn get_runs(&self, params: GetRunsParams) -> Result<Vec<Run>> {
+        let mut conn = self.pool.get().map_err(|e| anyhow::anyhow!("{}", e))?;
+        let limit = params.limit.unwrap_or(50).min(500);
+        let mut query = runs::table.into_boxed();
+        if let Some(solution_id) = params.solution_id {
+            query = query.filter(runs::solution_id.eq(solution_id));
+        }
+        if let Some(testcase_id) = params.testcase_id {
+            query = query.filter(runs::testcase_id.eq(testcase_id));
+        }
+        if let Some(verdict) = params.verdict {
+            query = query.filter(runs::verdict.eq(verdict));
+        }
+        let runs = query
+            .order(runs::create_datetime.desc())
+            .limit(limit.into())
+            .select(Run::as_select())
+            .load::<Run>(&mut conn)?;
+        Ok(runs)
+    }
+    /// Path of the output kept for a run, `None` if the solution is not run
+    pub fn get_run_output_filepath(&self, run_id: &str) -> Result<Option<PathBuf>> {
+        let mut conn = self.pool.get().map_err(|e| anyhow::anyhow!("{}", e))?;
+        let output_file = runs::table
+            .filter(runs::id.eq(run_id))
+            .select(runs::output_file)
+            .first::<Option<String>>(&mut conn)?;
+        Ok(output_file.map(|filename| self.get_run_output_folder().join(filename)))
+    }
     pub fn get_language_item(&self, language: &str) -> Result<AdvLanguageItem> {
         let config = self.config.read().unwrap();
         let language_config = config
@@ -682,3 +898,199 @@ impl DatabaseRepo {
         Ok(languages)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use diesel_migrations::MigrationHarness;
+
+    use super::*;
+    use crate::database::config::WorkspaceLocalDeserialized;
+
+    fn test_repo() -> DatabaseRepo {
+        // a query may hold more than one connection, which would each open their own `:memory:` database
+        let base_folder = std::env::temp_dir().join(format!("algorimejo-db-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&base_folder).unwrap();
+        let manager = ConnectionManager::<SqliteConnection>::new(
+            base_folder.join("database.sqlite").display().to_string(),
+        );
+        let pool = Pool::builder().build(manager).unwrap();
+        pool.get()
+            .unwrap()
+            .run_pending_migrations(crate::setup::MIGRATIONS)
+            .unwrap();
+        DatabaseRepo::new(
+            pool,
+            base_folder,
+            WorkspaceLocalDeserialized::default().into(),
+        )
+    }
+
+    fn create_test_problem(repo: &DatabaseRepo) -> String {
+        repo.create_problem(CreateProblemParams {
+            name: "A".to_string(),
+            url: None,
+            group: None,
+            statement: None,
+            checker: None,
+            time_limit: 1000,
+            memory_limit: 262144,
+            interactive: false,
+            input_file: None,
+            output_file: None,
+            initial_solution: None,
+        })
+        .unwrap()
+        .problem
+        .id
+    }
+
+    fn update(repo: &DatabaseRepo, problem_id: &str, changeset: &str) {
+        repo.update_problem(problem_id, serde_json::from_str(changeset).unwrap())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_clear_file_io() {
+        let repo = test_repo();
+        let id = create_test_problem(&repo);
+        update(
+            &repo,
+            &id,
+            r#"{"input_file": "a.in", "output_file": "a.out"}"#,
+        );
+        let problem = repo.get_problem(&id).unwrap();
+        assert_eq!(problem.input_file.as_deref(), Some("a.in"));
+        assert_eq!(problem.output_file.as_deref(), Some("a.out"));
+
+        // a missing field leaves the file name alone
+        update(&repo, &id, r#"{"name": "B"}"#);
+        let problem = repo.get_problem(&id).unwrap();
+        assert_eq!(problem.input_file.as_deref(), Some("a.in"));
+
+        update(&repo, &id, r#"{"input_file": null, "output_file": null}"#);
+        let problem = repo.get_problem(&id).unwrap();
+        assert_eq!(problem.input_file, None);
+        assert_eq!(problem.output_file, None);
+    }
+
+    #[test]
+    fn test_clear_interactor() {
+        let repo = test_repo();
+        let id = create_test_problem(&repo);
+        update(&repo, &id, r#"{"interactive": true, "interactor": "i"}"#);
+        assert_eq!(
+            repo.get_problem(&id).unwrap().interactor.as_deref(),
+            Some("i")
+        );
+
+        update(&repo, &id, r#"{"interactive": false, "interactor": null}"#);
+        let problem = repo.get_problem(&id).unwrap();
+        assert!(!problem.interactive);
+        assert_eq!(problem.interactor, None);
+    }
+
+    #[test]
+    fn test_clear_validator() {
+        let repo = test_repo();
+        let id = create_test_problem(&repo);
+        update(&repo, &id, r#"{"validator": "v"}"#);
+        assert_eq!(
+            repo.get_problem(&id).unwrap().validator.as_deref(),
+            Some("v")
+        );
+
+        update(&repo, &id, r#"{"validator": null}"#);
+        assert_eq!(repo.get_problem(&id).unwrap().validator, None);
+    }
+
+    #[test]
+    fn test_remove_testcase_recipe() {
+        let repo = test_repo();
+        let problem_id = create_test_problem(&repo);
+        let id = repo.create_testcase(&problem_id).unwrap().id;
+        let update = |changeset: &str| {
+            repo.update_testcase(&id, serde_json::from_str(changeset).unwrap())
+                .unwrap()
+        };
+
+        update(r#"{"generator": "g", "generator_args": "10", "reference": "r"}"#);
+        update(r#"{"generator_args": "20"}"#);
+        let testcase = repo.get_testcase(&id).unwrap();
+        assert_eq!(testcase.generator.as_deref(), Some("g"));
+        assert_eq!(testcase.generator_args.as_deref(), Some("20"));
+        assert_eq!(testcase.reference.as_deref(), Some("r"));
+
+        update(r#"{"generator": null, "generator_args": null, "reference": null}"#);
+        let testcase = repo.get_testcase(&id).unwrap();
+        assert_eq!(testcase.generator, None);
+        assert_eq!(testcase.generator_args, None);
+        assert_eq!(testcase.reference, None);
+    }
+
+    /// Record a run of a new solution on a new testcase, returns the path of its kept output
+    fn create_test_run(repo: &DatabaseRepo, problem_id: &str) -> (String, String, PathBuf) {
+        let solution_id = repo
+            .create_solution(
+                problem_id,
+                CreateSolutionParams {
+                    author: None,
+                    name: "main".to_string(),
+                    language: "cpp 17".to_string(),
+                    content: None,
+                },
+            )
+            .unwrap()
+            .solution
+            .id;
+        let testcase_id = repo.create_testcase(problem_id).unwrap().id;
+        let output_file = repo.base_folder.join(format!("{}.out", Uuid::new_v4()));
+        std::fs::write(&output_file, "3\n").unwrap();
+        let run = repo
+            .create_run(CreateRunParams {
+                solution_id: &solution_id,
+                testcase_id: &testcase_id,
+                source_hash: "hash",
+                verdict: "AC",
+                time_millis: Some(10),
+                memory_kib: None,
+                checker_message: None,
+                output_file: Some(&output_file),
+            })
+            .unwrap();
+        let kept = repo.get_run_output_filepath(&run.id).unwrap().unwrap();
+        assert!(kept.exists());
+        (solution_id, testcase_id, kept)
+    }
+
+    #[test]
+    fn test_remove_run_outputs() {
+        let repo = test_repo();
+        let problem_id = create_test_problem(&repo);
+
+        let runs_of = |solution_id: Option<&str>, testcase_id: Option<&str>| {
+            repo.get_runs(GetRunsParams {
+                solution_id: solution_id.map(str::to_string),
+                testcase_id: testcase_id.map(str::to_string),
+                verdict: None,
+                limit: None,
+            })
+            .unwrap()
+        };
+
+        let (solution_id, testcase_id, output) = create_test_run(&repo, &problem_id);
+        repo.delete_solution(&solution_id).unwrap();
+        assert!(!output.exists());
+        assert!(runs_of(Some(&solution_id), None).is_empty());
+        assert!(runs_of(None, Some(&testcase_id)).is_empty());
+
+        let (solution_id, testcase_id, output) = create_test_run(&repo, &problem_id);
+        repo.delete_testcase(&testcase_id).unwrap();
+        assert!(!output.exists());
+        assert!(runs_of(Some(&solution_id), None).is_empty());
+
+        let (_, _, output) = create_test_run(&repo, &problem_id);
+        repo.delete_problem(&problem_id).unwrap();
+        assert!(!output.exists());
+        assert!(runs_of(None, None).is_empty());
+    }
+}
@@ -0,0 +1,280 @@
+//! Capture the backtrace of a runtime error by running the crashing testcase again under gdb in batch mode.
+//! With a debug compile command the solution is compiled again with debug info into its own directory,
+//! so the frames carry the source lines. gdb writes the backtrace to a log file of its own,
+//! apart from the stdout shared with the solution, which could print anything that looks like a frame:
+//!
+//! ```text
+//! #0  0x0000555555555149 in f (p=0x0) at /tmp/algorime-tt-1/debug/code.cpp:3
+//! #1  0x000055555555516a in main () at /tmp/algorime-tt-1/debug/code.cpp:8
+//! ```
+use anyhow::Result;
+use log::trace;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    database::config::AdvLanguageItem,
+    judge::{
+        compile, diagnostic::is_source_file, run_testcase, source_file_name, FileIo, JudgeEnv,
+        Submission, TestcaseData,
+    },
+    runner::run::{ProgramTermination, RunLimit},
+};
+
+/// gdb is much slower to start than the solution, so the time limit of the problem is not used
+pub const BACKTRACE_TIMEOUT_MILLIS: u128 = 12000;
+
+/// Placeholder of the file gdb logs the backtrace to
+const ENV_KEY_BACKTRACE_FILE: &str = "BACKTRACE_FILE";
+
+/// Logging is turned on after the run, so only the output of `bt` goes to the file.
+/// `set logging on` is kept over `set logging enabled on`, which gdb before 12 does not know
+const GDB_COMMAND: &str = "gdb -batch -nx -ex \"set pagination off\" -ex \"set width 0\" \
+    -ex \"set logging file %BACKTRACE_FILE\" -ex \"set logging overwrite on\" \
+    -ex \"set logging redirect on\" -ex run -ex \"set logging on\" -ex bt --args";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct StackFrame {
+    pub index: u32,
+    /// `??` if gdb can not resolve the symbol
+    pub function: String,
+    /// The source file, or the shared library if the frame has no debug info
+    pub file: Option<String>,
+    /// 1-based
+    pub line: Option<u32>,
+}
+
+/// The line of the solution where it crashed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct CrashSite {
+    /// 1-based
+    pub line: u32,
+    pub code: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct Backtrace {
+    /// Innermost first
+    pub frames: Vec<StackFrame>,
+    /// `None` if no frame is in the source of the solution
+    pub crash_site: Option<CrashSite>,
+}
+
+/// `#1  0x000055555555516a in main () at /tmp/code.cpp:8`, the address is missing in the innermost frame
+fn parse_frame(line: &str) -> Option<StackFrame> {
+    let (index, rest) = line.strip_prefix('#')?.split_once(char::is_whitespace)?;
+    let index = index.parse::<u32>().ok()?;
+    let mut rest = rest.trim();
+    if rest.starts_with("0x") {
+        rest = rest.split_once(" in ")?.1;
+    }
+    let (frame, file, line) = if let Some((frame, location)) = rest.rsplit_once(" at ") {
+        match location.rsplit_once(':') {
+            Some((file, line)) if line.parse::<u32>().is_ok() => {
+                (frame, Some(file.to_string()), line.parse::<u32>().ok())
+            }
+            _ => (frame, Some(location.to_string()), None),
+        }
+    } else if let Some((frame, library)) = rest.rsplit_once(" from ") {
+        (frame, Some(library.to_string()), None)
+    } else {
+        (rest, None, None)
+    };
+    // the arguments follow the name, which may contain parentheses itself like `(anonymous namespace)::f`
+    let function = frame
+        .split_once(" (")
+        .map_or(frame, |(function, _)| function);
+    Some(StackFrame {
+        index,
+        function: function.trim().to_string(),
+        file,
+        line,
+    })
+}
+
+/// Parse the frames printed by `bt`, the crash site is the innermost frame in a file named `source_file_name`
+pub fn parse_backtrace(output: &str, source: &str, source_file_name: &str) -> Backtrace {
+    let frames = output
+        .lines()
+        .filter_map(|line| parse_frame(line.trim()))
+        .collect::<Vec<_>>();
+    let crash_site = frames
+        .iter()
+        .filter(|frame| {
+            frame
+                .file
+                .as_deref()
+                .is_some_and(|file| is_source_file(file, source_file_name))
+        })
+        .find_map(|frame| frame.line)
+        .map(|line| CrashSite {
+            line,
+            code: source
+                .lines()
+                .nth(line.saturating_sub(1) as usize)
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+        });
+    Backtrace { frames, crash_site }
+}
+
+/// Run the testcase under gdb, returns `None` if the run is cancelled or gdb prints no backtrace
+pub async fn capture(
+    judge_env: &JudgeEnv,
+    submission: &Submission,
+    testcase: &TestcaseData,
+    io: &FileIo,
+    limit: RunLimit,
+) -> Result<Option<Backtrace>> {
+    let (mut debug_env, language) = match &submission.language.cmd_compile_debug {
+        Some(cmd_compile_debug) => {
+            let debug_dir = judge_env.work_dir.join("debug");
+            let mut debug_env = judge_env.in_dir(debug_dir.clone());
+            // the run command usually refers to the program through `%CWD`
+            debug_env
+                .env
+                .insert("CWD".to_string(), debug_dir.display().to_string());
+            let debug_submission = Submission {
+                language: AdvLanguageItem {
+                    cmd_compile: cmd_compile_debug.clone(),
+                    ..submission.language.clone()
+                },
+                source: submission.source.clone(),
+            };
+            if compile(&debug_env, &debug_submission).await?.is_some() {
+                trace!("failed to compile the solution with debug info");
+                return Ok(None);
+            }
+            (debug_env, debug_submission.language)
+        }
+        None => (judge_env.clone(), submission.language.clone()),
+    };
+    let language = AdvLanguageItem {
+        cmd_run: format!("{} {}", GDB_COMMAND, &language.cmd_run),
+        // gdb traces the solution with ptrace, which the sandbox forbids
+        sandbox: false,
+        ..language
+    };
+    let input_file = debug_env.work_dir.join(format!("case-{}.in", &testcase.id));
+    let output_file = debug_env
+        .work_dir
+        .join(format!("case-{}.gdb.out", &testcase.id));
+    let backtrace_file = debug_env.work_dir.join(format!("case-{}.bt", &testcase.id));
+    debug_env.env.insert(
+        ENV_KEY_BACKTRACE_FILE.to_string(),
+        backtrace_file.display().to_string(),
+    );
+    tokio::fs::create_dir_all(&debug_env.work_dir).await?;
+    tokio::fs::write(&input_file, &testcase.input).await?;
+    // a file left by an earlier capture must not be taken for the backtrace of this run
+    let _ = tokio::fs::remove_file(&backtrace_file).await;
+    let output = run_testcase(
+        &debug_env,
+        &language,
+        io,
+        &format!("{}-gdb", &testcase.id),
+        &input_file,
+        &output_file,
+        RunLimit {
+            timeout_millis: BACKTRACE_TIMEOUT_MILLIS,
+            memory_limit_kib: None,
+            ..limit
+        },
+        |_| {},
+        |_| {},
+    )
+    .await?;
+    if output.termination() == ProgramTermination::Cancelled {
+        return Ok(None);
+    }
+    // the file is missing if gdb could not even start, `bt` of a solution exiting normally logs `No stack.`
+    let gdb_output = match tokio::fs::read(&backtrace_file).await {
+        Ok(content) => String::from_utf8_lossy(&content).into_owned(),
+        Err(e) => {
+            trace!("gdb wrote no backtrace to {:?}: {}", &backtrace_file, e);
+            return Ok(None);
+        }
+    };
+    let source_file_name = source_file_name(&language);
+    let backtrace = parse_backtrace(&gdb_output, &submission.source, &source_file_name);
+    Ok((!backtrace.frames.is_empty()).then_some(backtrace))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::runner::cmd::parse_command_with_env;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_backtrace() {
+        let output = r#"Program received signal SIGABRT, Aborted.
+__pthread_kill_implementation (no_tid=0, signo=6, threadid=<optimized out>) at ./nptl/pthread_kill.c:44
+44	./nptl/pthread_kill.c: No such file or directory.
+#0  __pthread_kill_implementation (no_tid=0, signo=6, threadid=<optimized out>) at ./nptl/pthread_kill.c:44
+#1  0x00007ffff7c4526e in __GI_raise (sig=sig@entry=6) at ../sysdeps/posix/raise.c:26
+#2  0x00007ffff7e9c3f9 in ?? () from /lib/x86_64-linux-gnu/libstdc++.so.6
+#3  0x0000555555555189 in (anonymous namespace)::solve (n=3) at /tmp/algorime-tt-1/debug/code.cpp:5
+#4  0x00005555555551b0 in main () at /tmp/algorime-tt-1/debug/code.cpp:9
+"#;
+        let source = "#include<cassert>\nnamespace {\nvoid solve(int n) {\n\tint a[2];\n\tassert(n < 2);\n}\n}\nint main() {\n\tsolve(3);\n}";
+        let backtrace = parse_backtrace(output, source, "code.cpp");
+        assert_eq!(backtrace.frames.len(), 5);
+        assert_eq!(
+            backtrace.frames[0],
+            StackFrame {
+                index: 0,
+                function: "__pthread_kill_implementation".to_string(),
+                file: Some("./nptl/pthread_kill.c".to_string()),
+                line: Some(44),
+            }
+        );
+        assert_eq!(
+            backtrace.frames[2],
+            StackFrame {
+                index: 2,
+                function: "??".to_string(),
+                file: Some("/lib/x86_64-linux-gnu/libstdc++.so.6".to_string()),
+                line: None,
+            }
+        );
+        assert_eq!(backtrace.frames[3].function, "(anonymous namespace)::solve");
+        assert_eq!(
+            backtrace.crash_site,
+            Some(CrashSite {
+                line: 5,
+                code: "assert(n < 2);".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_gdb_logs_backtrace_to_file() {
+        let env = HashMap::from([(
+            ENV_KEY_BACKTRACE_FILE.to_string(),
+            "/tmp/a b/case-1.bt".to_string(),
+        )]);
+        let cmd = parse_command_with_env(&format!("{} ./code", GDB_COMMAND), &env).unwrap();
+        let args = cmd
+            .get_args()
+            .map(|arg| arg.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert!(args.contains(&"set logging file /tmp/a b/case-1.bt"));
+        let position = |arg| args.iter().position(|a| *a == arg).unwrap();
+        assert!(position("run") < position("set logging on"));
+        assert!(position("set logging on") < position("bt"));
+        assert_eq!(args.last(), Some(&"./code"));
+    }
+
+    #[test]
+    fn test_parse_backtrace_without_debug_info() {
+        let output = "#0  0x0000555555555131 in f() ()\n#1  0x0000555555555150 in main ()\n";
+        let backtrace = parse_backtrace(output, "", "code.cpp");
+        assert_eq!(backtrace.frames[0].function, "f()");
+        assert_eq!(backtrace.frames[1].file, None);
+        assert_eq!(backtrace.crash_site, None);
+    }
+}
@@ -0,0 +1,152 @@
+//! Compiled programs shared by every task of the workspace, so an unchanged source is not compiled again,
+//! even after a restart. Each entry is a directory named by the compile key, holding the files
+//! the compiler wrote into the working directory along with the source itself.
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use log::{trace, warn};
+
+/// Least recently used entries are removed once there are more of them
+const COMPILE_CACHE_CAPACITY: usize = 128;
+
+/// Written into each entry, records when it was used for the last time
+const LAST_USED_FILENAME: &str = ".last-used";
+
+/// 128-bit FNV-1a, the key must stay the same across restarts and Rust versions
+pub fn stable_hash(parts: &[&[u8]]) -> String {
+    const OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    let mut hash = OFFSET_BASIS;
+    for part in parts {
+        // the length keeps ("ab", "c") apart from ("a", "bc")
+        for byte in (part.len() as u64).to_le_bytes().iter().chain(part.iter()) {
+            hash ^= *byte as u128;
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    format!("{:032x}", hash)
+}
+
+/// Size and modification time of the regular files directly in `dir`
+pub type Snapshot = HashMap<PathBuf, (u64, Option<SystemTime>)>;
+
+pub async fn snapshot(dir: &Path) -> Result<Snapshot> {
+    let mut files = HashMap::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        if metadata.is_file() {
+            files.insert(entry.path(), (metadata.len(), metadata.modified().ok()));
+        }
+    }
+    Ok(files)
+}
+
+fn now_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone)]
+pub struct CompileCache {
+    dir: PathBuf,
+}
+
+impl CompileCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Copy the files of the entry into `work_dir`, returns false if there is no such entry
+    pub async fn restore(&self, key: &str, work_dir: &Path) -> Result<bool> {
+        let entry_dir = self.dir.join(key);
+        if !tokio::fs::try_exists(&entry_dir).await? {
+            return Ok(false);
+        }
+        let mut entries = tokio::fs::read_dir(&entry_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_name() != LAST_USED_FILENAME {
+                tokio::fs::copy(entry.path(), work_dir.join(entry.file_name())).await?;
+            }
+        }
+        tokio::fs::write(entry_dir.join(LAST_USED_FILENAME), now_millis().to_string()).await?;
+        trace!("restore {} from the compile cache into {:?}", key, work_dir);
+        Ok(true)
+    }
+
+    /// Save the files which are new or changed in `work_dir` since `before` was taken
+    pub async fn store(&self, key: &str, work_dir: &Path, before: &Snapshot) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        // the entry is complete once it is renamed, a half written one is never restored
+        let partial_dir = self
+            .dir
+            .join(format!("{}.{}.partial", key, uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&partial_dir).await?;
+        for (path, metadata) in snapshot(work_dir).await? {
+            if before.get(&path) == Some(&metadata) {
+                continue;
+            }
+            if let Some(filename) = path.file_name() {
+                tokio::fs::copy(&path, partial_dir.join(filename)).await?;
+            }
+        }
+        tokio::fs::write(
+            partial_dir.join(LAST_USED_FILENAME),
+            now_millis().to_string(),
+        )
+        .await?;
+        let entry_dir = self.dir.join(key);
+        let _ = tokio::fs::remove_dir_all(&entry_dir).await;
+        tokio::fs::rename(&partial_dir, &entry_dir).await?;
+        trace!("store {} into the compile cache", key);
+
+        if let Err(e) = self.evict().await {
+            warn!("failed to evict the compile cache: {}", e);
+        }
+        Ok(())
+    }
+
+    async fn evict(&self) -> Result<()> {
+        let mut entries = Vec::new();
+        let mut dir = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            // still being written by another compilation
+            if entry.file_name().to_string_lossy().ends_with(".partial") {
+                continue;
+            }
+            let last_used = tokio::fs::read_to_string(entry.path().join(LAST_USED_FILENAME))
+                .await
+                .ok()
+                .and_then(|millis| millis.trim().parse::<u128>().ok())
+                .unwrap_or_default();
+            entries.push((last_used, entry.path()));
+        }
+        if entries.len() <= COMPILE_CACHE_CAPACITY {
+            return Ok(());
+        }
+        entries.sort();
+        for (_, path) in &entries[..entries.len() - COMPILE_CACHE_CAPACITY] {
+            trace!("evict {:?} from the compile cache", path);
+            tokio::fs::remove_dir_all(path).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_hash() {
+        assert_eq!(stable_hash(&[]), "6c62272e07bb014262b821756295c58d");
+        assert_ne!(stable_hash(&[b"ab", b"c"]), stable_hash(&[b"a", b"bc"]));
+        assert_eq!(stable_hash(&[b"code"]), stable_hash(&[b"code"]));
+    }
+}
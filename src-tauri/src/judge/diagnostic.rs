@@ -0,0 +1,201 @@
+//! Parse the diagnostics printed by compilers, so a compile error can be shown in the solution document.
+//!
+//! GCC, Clang and javac print one line per diagnostic:
+//!
+//! ```text
+//! code.cpp:3:5: error: 'x' was not declared in this scope
+//! Main.java:3: error: ';' expected
+//! ```
+//!
+//! rustc prints the location on the line after the message:
+//!
+//! ```text
+//! error[E0425]: cannot find value `x` in this scope
+//!  --> code.rs:2:5
+//! ```
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct Diagnostic {
+    /// `None` if the diagnostic is in the source of the solution
+    pub file: Option<String>,
+    /// 1-based
+    pub line: u32,
+    /// 1-based, `None` if the compiler does not report it
+    pub column: Option<u32>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Markers following the location in the GCC style, the first one found in a line is used
+const SEVERITY_MARKERS: [(&str, Severity); 4] = [
+    (": fatal error: ", Severity::Error),
+    (": error: ", Severity::Error),
+    (": warning: ", Severity::Warning),
+    (": note: ", Severity::Note),
+];
+
+/// `file:line:column` or `file:line`, a drive letter in the file is kept
+pub fn parse_location(location: &str) -> Option<(&str, u32, Option<u32>)> {
+    let mut parts = location.rsplitn(3, ':');
+    let last = parts.next()?.trim().parse::<u32>().ok()?;
+    let rest = parts.next()?;
+    match (rest.trim().parse::<u32>(), parts.next()) {
+        (Ok(line), Some(file)) if !file.is_empty() => Some((file, line, Some(last))),
+        _ => {
+            let file = location.rsplit_once(':')?.0;
+            (!file.is_empty()).then_some((file, last, None))
+        }
+    }
+}
+
+fn parse_gcc_line(line: &str) -> Option<(&str, u32, Option<u32>, Severity, &str)> {
+    let (index, marker, severity) = SEVERITY_MARKERS
+        .iter()
+        .filter_map(|(marker, severity)| line.find(marker).map(|i| (i, *marker, *severity)))
+        .min_by_key(|(i, _, _)| *i)?;
+    let (file, line_number, column) = parse_location(&line[..index])?;
+    Some((
+        file,
+        line_number,
+        column,
+        severity,
+        &line[index + marker.len()..],
+    ))
+}
+
+/// `error[E0425]: message`, returns the severity and the message
+fn parse_rustc_header(line: &str) -> Option<(Severity, &str)> {
+    let (head, message) = line.split_once(": ")?;
+    let name = head.split_once('[').map_or(head, |(name, _)| name);
+    let severity = match name {
+        "error" => Severity::Error,
+        "warning" => Severity::Warning,
+        _ => return None,
+    };
+    Some((severity, message))
+}
+
+/// Whether `file` printed by a tool is the source of the solution, named `source_file_name`
+pub fn is_source_file(file: &str, source_file_name: &str) -> bool {
+    // tools on Windows may print either separator
+    file.trim().rsplit(['/', '\\']).next() == Some(source_file_name)
+}
+
+/// Parse every diagnostic with a location in the output of a compiler.
+/// Diagnostics in a file named `source_file_name` are in the source of the solution.
+pub fn parse_diagnostics(output: &str, source_file_name: &str) -> Vec<Diagnostic> {
+    let to_file =
+        |file: &str| (!is_source_file(file, source_file_name)).then(|| file.trim().to_string());
+    let lines = output.lines().collect::<Vec<_>>();
+    let mut diagnostics = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if let Some((file, line_number, column, severity, message)) = parse_gcc_line(line) {
+            diagnostics.push(Diagnostic {
+                file: to_file(file),
+                line: line_number,
+                column,
+                severity,
+                message: message.trim().to_string(),
+            });
+        } else if let Some((severity, message)) = parse_rustc_header(line) {
+            let Some(location) = lines
+                .get(i + 1)
+                .and_then(|next| next.trim_start().strip_prefix("--> "))
+            else {
+                continue;
+            };
+            if let Some((file, line_number, column)) = parse_location(location) {
+                diagnostics.push(Diagnostic {
+                    file: to_file(file),
+                    line: line_number,
+                    column,
+                    severity,
+                    message: message.trim().to_string(),
+                });
+            }
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gcc_diagnostics() {
+        let output = r#"/tmp/algorime-tt-1/code.cpp: In function 'int main()':
+/tmp/algorime-tt-1/code.cpp:4:5: error: 'x' was not declared in this scope
+    4 |     x = 1;
+      |     ^
+/tmp/algorime-tt-1/code.cpp:5:12: warning: unused variable 'y' [-Wunused-variable]
+In file included from /tmp/algorime-tt-1/code.cpp:1:
+/usr/include/a.h:2:10: fatal error: b.h: No such file or directory
+C:\Users\me\code.cpp:7: note: declared here
+"#;
+        assert_eq!(
+            parse_diagnostics(output, "code.cpp"),
+            vec![
+                Diagnostic {
+                    file: None,
+                    line: 4,
+                    column: Some(5),
+                    severity: Severity::Error,
+                    message: "'x' was not declared in this scope".to_string(),
+                },
+                Diagnostic {
+                    file: None,
+                    line: 5,
+                    column: Some(12),
+                    severity: Severity::Warning,
+                    message: "unused variable 'y' [-Wunused-variable]".to_string(),
+                },
+                Diagnostic {
+                    file: Some("/usr/include/a.h".to_string()),
+                    line: 2,
+                    column: Some(10),
+                    severity: Severity::Error,
+                    message: "b.h: No such file or directory".to_string(),
+                },
+                Diagnostic {
+                    file: None,
+                    line: 7,
+                    column: None,
+                    severity: Severity::Note,
+                    message: "declared here".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_rustc_diagnostics() {
+        let output = r#"error[E0425]: cannot find value `x` in this scope
+ --> /tmp/algorime-tt-1/code.rs:2:5
+  |
+2 |     x
+  |     ^ not found in this scope
+
+error: aborting due to 1 previous error
+"#;
+        assert_eq!(
+            parse_diagnostics(output, "code.rs"),
+            vec![Diagnostic {
+                file: None,
+                line: 2,
+                column: Some(5),
+                severity: Severity::Error,
+                message: "cannot find value `x` in this scope".to_string(),
+            }]
+        );
+    }
+}
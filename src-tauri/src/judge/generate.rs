@@ -0,0 +1,83 @@
+//! Generated testcases (Polygon-style): the input is printed by a generator run with the arguments
+//! of the recipe, and the answer by a reference solution, so large testcases are reproduced instead of stored.
+use anyhow::Result;
+use log::trace;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    judge::{
+        compile_tool,
+        stress::{generate_answer, generate_input, HelperOutput, StressProgram},
+        FileIo,
+    },
+    runner::run::{ProgramOutput, RunLimit},
+};
+
+#[derive(Debug, Clone)]
+pub struct GenerateTask {
+    pub generator: StressProgram,
+    pub generator_args: String,
+    pub reference: StressProgram,
+    /// Files the reference solution uses instead of stdin and stdout
+    pub io: FileIo,
+    pub limit: RunLimit,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "type")]
+pub enum GenerateOutcome {
+    Generated {
+        input: String,
+        answer: String,
+    },
+    /// The generator did not exit normally
+    GeneratorFailed {
+        output: ProgramOutput,
+    },
+    /// The reference solution did not exit normally on the generated input
+    ReferenceFailed {
+        output: ProgramOutput,
+    },
+    Cancelled,
+}
+
+/// Compile the generator and the reference solution, then produce the input and the answer of `testcase_id`
+pub async fn generate(task: &GenerateTask, testcase_id: &str) -> Result<GenerateOutcome> {
+    for (program, name) in [
+        (&task.generator, "generator"),
+        (&task.reference, "reference solution"),
+    ] {
+        if compile_tool(&program.env, &program.submission, name)
+            .await?
+            .is_some()
+        {
+            return Ok(GenerateOutcome::Cancelled);
+        }
+    }
+    let input = match generate_input(
+        &task.generator,
+        &task.generator_args,
+        testcase_id,
+        task.limit,
+    )
+    .await?
+    {
+        HelperOutput::Printed(input) => input,
+        HelperOutput::Failed(output) => return Ok(GenerateOutcome::GeneratorFailed { output }),
+        HelperOutput::Cancelled => return Ok(GenerateOutcome::Cancelled),
+    };
+    let answer =
+        match generate_answer(&task.reference, &task.io, testcase_id, &input, task.limit).await? {
+            HelperOutput::Printed(answer) => answer,
+            HelperOutput::Failed(output) => return Ok(GenerateOutcome::ReferenceFailed { output }),
+            HelperOutput::Cancelled => return Ok(GenerateOutcome::Cancelled),
+        };
+    trace!(
+        "generate {}: {} bytes of input, {} bytes of answer",
+        testcase_id,
+        input.len(),
+        answer.len()
+    );
+    Ok(GenerateOutcome::Generated { input, answer })
+}
@@ -0,0 +1,273 @@
+//! Shrink a failing input by delta debugging, first removing whole lines and then single tokens.
+//! A candidate is kept as long as the target solution still fails on it,
+//! with the output of the reference solution as the answer.
+use anyhow::{anyhow, Result};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    judge::{
+        stress::{compare, compile_programs, Comparison, StressProgram},
+        JudgeMode, Verdict,
+    },
+    runner::run::RunLimit,
+};
+
+/// Every candidate is written to the same files
+const MINIMIZE_TESTCASE_ID: &str = "minimize";
+
+#[derive(Debug, Clone)]
+pub struct MinimizeTask {
+    pub target: StressProgram,
+    pub reference: StressProgram,
+    pub mode: JudgeMode,
+    pub limit: RunLimit,
+    /// How many candidates are tested at most
+    pub max_runs: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "type")]
+pub enum MinimizeOutcome {
+    /// The smallest failing input found, along with the answer and the verdict on it
+    Minimized {
+        input: String,
+        answer: String,
+        verdict: Box<Verdict>,
+        runs: u32,
+    },
+    /// The target solution is accepted on the original input
+    NotFailing,
+    /// The reference solution does not exit normally on the original input
+    ReferenceFailed,
+    Cancelled,
+}
+
+/// Delta debugging: split the units into chunks and try to remove one chunk at a time,
+/// the chunks get smaller whenever none of them can be removed
+#[derive(Debug, Clone)]
+pub struct Ddmin<T> {
+    units: Vec<T>,
+    granularity: usize,
+    chunk: usize,
+    is_done: bool,
+}
+
+impl<T: Clone> Ddmin<T> {
+    pub fn new(units: Vec<T>) -> Self {
+        Self {
+            is_done: units.len() < 2,
+            units,
+            granularity: 2,
+            chunk: 0,
+        }
+    }
+
+    fn chunk_range(&self) -> std::ops::Range<usize> {
+        let size = self.units.len().div_ceil(self.granularity);
+        let start = (self.chunk * size).min(self.units.len());
+        start..(start + size).min(self.units.len())
+    }
+
+    /// The units without the current chunk, `None` once no single chunk can be removed
+    pub fn candidate(&self) -> Option<Vec<T>> {
+        if self.is_done {
+            return None;
+        }
+        let range = self.chunk_range();
+        let mut candidate = self.units[..range.start].to_vec();
+        candidate.extend_from_slice(&self.units[range.end..]);
+        Some(candidate)
+    }
+
+    /// Report whether the current candidate still fails
+    pub fn report(&mut self, is_failing: bool) {
+        if is_failing {
+            let range = self.chunk_range();
+            self.units.drain(range);
+            self.granularity = (self.granularity - 1).max(2);
+            self.chunk = 0;
+            self.is_done = self.units.len() < 2;
+            return;
+        }
+        self.chunk += 1;
+        if self.chunk_range().is_empty() {
+            if self.granularity >= self.units.len() {
+                self.is_done = true;
+            } else {
+                self.granularity = (self.granularity * 2).min(self.units.len());
+                self.chunk = 0;
+            }
+        }
+    }
+
+    pub fn into_units(self) -> Vec<T> {
+        self.units
+    }
+}
+
+fn join_lines(lines: &[String]) -> String {
+    let mut input = lines.join("\n");
+    input.push('\n');
+    input
+}
+
+/// Tokens are tagged with their line, tokens on the same line are joined by a space
+fn join_tokens(tokens: &[(usize, String)]) -> String {
+    let mut input = String::new();
+    for (i, (line, token)) in tokens.iter().enumerate() {
+        if i > 0 {
+            input.push(if tokens[i - 1].0 == *line { ' ' } else { '\n' });
+        }
+        input.push_str(token);
+    }
+    input.push('\n');
+    input
+}
+
+struct Minimizer<'a> {
+    task: &'a MinimizeTask,
+    runs: u32,
+    /// The smallest failing input so far, with its answer and verdict
+    best: (String, String, Verdict),
+}
+
+impl Minimizer<'_> {
+    /// Whether the target solution still fails on the input, `None` if the budget runs out
+    async fn test(&mut self, input: String) -> Result<Option<bool>> {
+        if self.runs >= self.task.max_runs || self.task.target.env.cancel.is_cancelled() {
+            return Ok(None);
+        }
+        self.runs += 1;
+        match compare(
+            &self.task.reference,
+            &self.task.target,
+            &self.task.mode,
+            self.task.limit,
+            MINIMIZE_TESTCASE_ID,
+            input.clone(),
+        )
+        .await?
+        {
+            Comparison::Mismatch { answer, verdict } => {
+                trace!("minimize: {} bytes still fail", input.len());
+                self.best = (input, answer, verdict);
+                Ok(Some(true))
+            }
+            // an input the reference solution rejects is out of the constraints
+            Comparison::Accepted | Comparison::ReferenceFailed(_) => Ok(Some(false)),
+            Comparison::Cancelled => Ok(None),
+        }
+    }
+
+    async fn reduce<T: Clone>(&mut self, units: Vec<T>, join: fn(&[T]) -> String) -> Result<()> {
+        let mut ddmin = Ddmin::new(units);
+        while let Some(candidate) = ddmin.candidate() {
+            match self.test(join(&candidate)).await? {
+                Some(is_failing) => ddmin.report(is_failing),
+                None => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compile both solutions and minimize `input`, on which the target solution is expected to fail
+pub async fn minimize(task: &MinimizeTask, input: String) -> Result<MinimizeOutcome> {
+    if !matches!(task.mode, JudgeMode::Standard { .. }) {
+        return Err(anyhow!("Minimizing interactive problems is not supported"));
+    }
+    let programs = [
+        (&task.reference, "reference solution"),
+        (&task.target, "solution"),
+    ];
+    if !compile_programs(&programs, &task.mode).await? {
+        return Ok(MinimizeOutcome::Cancelled);
+    }
+    let (answer, verdict) = match compare(
+        &task.reference,
+        &task.target,
+        &task.mode,
+        task.limit,
+        MINIMIZE_TESTCASE_ID,
+        input.clone(),
+    )
+    .await?
+    {
+        Comparison::Mismatch { answer, verdict } => (answer, verdict),
+        Comparison::Accepted => return Ok(MinimizeOutcome::NotFailing),
+        Comparison::ReferenceFailed(_) => return Ok(MinimizeOutcome::ReferenceFailed),
+        Comparison::Cancelled => return Ok(MinimizeOutcome::Cancelled),
+    };
+
+    let mut minimizer = Minimizer {
+        task,
+        runs: 0,
+        best: (input, answer, verdict),
+    };
+    let lines = minimizer.best.0.lines().map(str::to_string).collect();
+    minimizer.reduce(lines, join_lines).await?;
+    let tokens = minimizer
+        .best
+        .0
+        .lines()
+        .enumerate()
+        .flat_map(|(line, content)| {
+            content
+                .split_whitespace()
+                .map(move |token| (line, token.to_string()))
+        })
+        .collect();
+    minimizer.reduce(tokens, join_tokens).await?;
+
+    if task.target.env.cancel.is_cancelled() {
+        return Ok(MinimizeOutcome::Cancelled);
+    }
+    let (input, answer, verdict) = minimizer.best;
+    Ok(MinimizeOutcome::Minimized {
+        input,
+        answer,
+        verdict: Box::new(verdict),
+        runs: minimizer.runs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ddmin<T: Clone>(units: Vec<T>, is_failing: impl Fn(&[T]) -> bool) -> Vec<T> {
+        let mut ddmin = Ddmin::new(units);
+        while let Some(candidate) = ddmin.candidate() {
+            ddmin.report(is_failing(&candidate));
+        }
+        ddmin.into_units()
+    }
+
+    #[test]
+    fn test_ddmin_single_cause() {
+        let units: Vec<u32> = (0..100).collect();
+        assert_eq!(ddmin(units, |c| c.contains(&42)), vec![42]);
+    }
+
+    #[test]
+    fn test_ddmin_pair_of_causes() {
+        let units: Vec<u32> = (0..64).collect();
+        assert_eq!(
+            ddmin(units, |c| c.contains(&3) && c.contains(&60)),
+            vec![3, 60]
+        );
+    }
+
+    #[test]
+    fn test_join_tokens() {
+        let tokens = vec![
+            (0, "3".to_string()),
+            (1, "1".to_string()),
+            (1, "2".to_string()),
+            (3, "x".to_string()),
+        ];
+        assert_eq!(join_tokens(&tokens), "3\n1 2\nx\n");
+    }
+}
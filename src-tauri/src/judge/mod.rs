@@ -0,0 +1,1122 @@
+//! The judging pipeline: compile a solution, run it against a testcase and check its output.
+//! Nothing here depends on tauri, callers resolve the solution, testcase and checker beforehand.
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Result};
+use log::{trace, warn};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    sync::Semaphore,
+    task::JoinSet,
+};
+
+use crate::{
+    database::config::{AdvLanguageItem, BuildStep},
+    judge::{cache::CompileCache, sanitizer::SanitizerReport},
+    runner::{
+        cancel::CancelToken,
+        cmd::parse_command_with_env,
+        interact::{launch_interactive, InteractiveOutput},
+        run::{
+            launch_program, launch_program_without_input, read_program_output, ProgramOutput,
+            ProgramSimpleOutput, ProgramTermination, RunLimit, WATCHDOG_INTERVAL,
+        },
+        sandbox,
+    },
+};
+
+pub mod backtrace;
+pub mod cache;
+pub mod diagnostic;
+pub mod generate;
+pub mod minimize;
+pub mod sanitizer;
+pub mod stress;
+pub mod validate;
+pub mod verdict;
+
+pub use verdict::{TestlibResult, Verdict};
+
+pub const COMPILE_TIMEOUT_MILLIS: u128 = 12000;
+pub const HOOK_TIMEOUT_MILLIS: u128 = 3000;
+pub const CHECKER_TIMEOUT_MILLIS: u128 = 12000;
+
+/// Records the hash of the last source compiled successfully in a working directory
+const COMPILE_STAMP_FILENAME: &str = "compile.stamp";
+
+/// Stderr of the solution kept to find a sanitizer report, the report comes before anything printed later
+const KEPT_STDERR_BYTES: usize = 64 * 1024;
+
+/// Search paths of headers read by gcc and clang, used to provide `testlib.h`
+const INCLUDE_PATH_ENV_KEYS: [&str; 2] = ["CPLUS_INCLUDE_PATH", "C_INCLUDE_PATH"];
+
+/// Working directory and environment variables shared by every step of a judge task
+#[derive(Debug, Clone)]
+pub struct JudgeEnv {
+    work_dir: PathBuf,
+    env: HashMap<String, String>,
+    cancel: CancelToken,
+    include_dir: Option<PathBuf>,
+    compile_cache: Option<CompileCache>,
+    capture_backtrace: bool,
+}
+
+impl JudgeEnv {
+    /// `%CWD` is always set to the working directory
+    pub fn new(work_dir: PathBuf, env: HashMap<String, String>, cancel: CancelToken) -> Self {
+        let mut env = env;
+        env.insert("CWD".to_string(), work_dir.display().to_string());
+        Self {
+            work_dir,
+            env,
+            cancel,
+            include_dir: None,
+            compile_cache: None,
+            capture_backtrace: false,
+        }
+    }
+
+    /// Make the headers in `dir` available to every program compiled in this environment
+    pub fn with_include_dir(self, dir: PathBuf) -> Self {
+        Self {
+            include_dir: Some(dir),
+            ..self
+        }
+    }
+
+    /// Share the programs compiled in this environment with every environment using the same cache
+    pub fn with_compile_cache(self, cache: CompileCache) -> Self {
+        Self {
+            compile_cache: Some(cache),
+            ..self
+        }
+    }
+
+    /// Run a testcase ending with a runtime error again under gdb, see [`backtrace`]
+    pub fn with_backtrace_capture(self) -> Self {
+        Self {
+            capture_backtrace: true,
+            ..self
+        }
+    }
+
+    pub fn work_dir(&self) -> &Path {
+        &self.work_dir
+    }
+
+    /// Run programs in another directory, `%CWD` still refers to the original working directory
+    fn in_dir(&self, dir: PathBuf) -> Self {
+        Self {
+            work_dir: dir,
+            ..self.clone()
+        }
+    }
+
+    /// Kill the programs with another token instead of the one of the task
+    fn with_cancel(self, cancel: CancelToken) -> Self {
+        Self { cancel, ..self }
+    }
+
+    fn command(&self, commands: &str, extra_env: &[(&str, String)]) -> Result<Command> {
+        let mut env = self.env.clone();
+        for (key, value) in extra_env {
+            env.insert(key.to_string(), value.clone());
+        }
+        let mut cmd = parse_command_with_env(commands, &env).map_err(|e| anyhow!(e))?;
+        cmd.current_dir(&self.work_dir);
+        if let Some(include_dir) = &self.include_dir {
+            for key in INCLUDE_PATH_ENV_KEYS {
+                let paths = std::iter::once(include_dir.clone()).chain(
+                    std::env::var_os(key)
+                        .map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+                        .unwrap_or_default(),
+                );
+                cmd.env(key, std::env::join_paths(paths)?);
+            }
+        }
+        Ok(cmd)
+    }
+}
+
+/// The solution to judge
+#[derive(Debug, Clone)]
+pub struct Submission {
+    pub language: AdvLanguageItem,
+    pub source: String,
+}
+
+/// Files the solution uses instead of stdin and stdout, relative to the directory it runs in
+#[derive(Debug, Clone, Default)]
+pub struct FileIo {
+    pub input_file: Option<String>,
+    pub output_file: Option<String>,
+}
+
+impl FileIo {
+    fn is_stdio(&self) -> bool {
+        self.input_file.is_none() && self.output_file.is_none()
+    }
+}
+
+/// Only plain file names are accepted, the solution must not touch anything outside its directory
+fn plain_file_name(name: &str) -> Result<&str> {
+    if Path::new(name).file_name().and_then(|n| n.to_str()) == Some(name) {
+        Ok(name)
+    } else {
+        Err(anyhow!("Invalid I/O file name: {}", name))
+    }
+}
+
+/// A testlib compatible checker
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum CheckerProgram {
+    /// An executable shipped with the app
+    Bundled(PathBuf),
+    /// Written by the user, compiled in its own working directory
+    Custom {
+        checker_env: JudgeEnv,
+        checker: Submission,
+    },
+}
+
+/// How the output of a solution is judged
+#[derive(Debug, Clone)]
+pub enum JudgeMode {
+    /// The solution reads the input, its output is compared by a testlib compatible checker
+    Standard { checker: CheckerProgram, io: FileIo },
+    /// The solution talks to an interactor, whose exit code decides the verdict like a checker.
+    /// The interactor is compiled in its own working directory.
+    Interactive {
+        interactor_env: JudgeEnv,
+        interactor: Submission,
+    },
+}
+
+impl JudgeMode {
+    /// Compile the custom checker or the interactor if there is one
+    async fn prepare(&self) -> Result<Option<Verdict>> {
+        let (env, submission, name) = match self {
+            JudgeMode::Standard {
+                checker:
+                    CheckerProgram::Custom {
+                        checker_env,
+                        checker,
+                    },
+                ..
+            } => (checker_env, checker, "checker"),
+            JudgeMode::Interactive {
+                interactor_env,
+                interactor,
+            } => (interactor_env, interactor, "interactor"),
+            _ => return Ok(None),
+        };
+        compile_tool(env, submission, name).await
+    }
+}
+
+/// Compile a program the judge relies on, such as a checker.
+/// A failed compilation is an error instead of a verdict, only the cancellation is returned.
+async fn compile_tool(
+    env: &JudgeEnv,
+    submission: &Submission,
+    name: &str,
+) -> Result<Option<Verdict>> {
+    match compile(env, submission).await? {
+        None => Ok(None),
+        Some(Verdict::Cancelled) => Ok(Some(Verdict::Cancelled)),
+        Some(Verdict::CompileError {
+            compiler_stderr, ..
+        }) => Err(anyhow!(
+            "Failed to compile the {}: {}",
+            name,
+            compiler_stderr
+        )),
+        Some(_) => Err(anyhow!(
+            "Failed to compile the {}: time limit exceeded",
+            name
+        )),
+    }
+}
+
+/// A testcase whose contents are already loaded
+#[derive(Debug, Clone)]
+pub struct TestcaseData {
+    pub id: String,
+    pub input: String,
+    pub answer: String,
+}
+
+/// Name of the source file written into the working directory
+fn source_file_name(language: &AdvLanguageItem) -> String {
+    format!("code.{}", language.base.extension())
+}
+
+/// Everything the compiled program depends on: the platform, the language, the headers and the source
+fn compile_key(judge_env: &JudgeEnv, submission: &Submission) -> String {
+    let include_dir = judge_env
+        .include_dir
+        .as_ref()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_default();
+    // the environment of a step is ordered, so is its serialization
+    let build_steps = serde_json::to_string(&submission.language.build_steps).unwrap_or_default();
+    cache::stable_hash(&[
+        std::env::consts::OS.as_bytes(),
+        std::env::consts::ARCH.as_bytes(),
+        submission.language.base.extension().as_bytes(),
+        submission.language.cmd_compile.as_bytes(),
+        build_steps.as_bytes(),
+        include_dir.as_bytes(),
+        submission.source.as_bytes(),
+    ])
+}
+
+/// `cmd_compile` followed by the build steps of the language, a blank `cmd_compile` is skipped
+fn build_steps(language: &AdvLanguageItem) -> Vec<BuildStep> {
+    let compile = (!language.cmd_compile.trim().is_empty()).then(|| BuildStep {
+        name: "compile".to_string(),
+        command: language.cmd_compile.clone(),
+        timeout_millis: None,
+        env: Default::default(),
+        artifacts: vec![],
+    });
+    compile
+        .into_iter()
+        .chain(language.build_steps.iter().cloned())
+        .collect()
+}
+
+/// Run a build step in the working directory, returns the verdict if it fails
+/// or if it does not produce all of its artifacts
+async fn run_build_step(
+    judge_env: &JudgeEnv,
+    step: &BuildStep,
+    source_file: &Path,
+    source_file_name: &str,
+) -> Result<Option<Verdict>> {
+    let extra_env = std::iter::once(("SRC", source_file.display().to_string()))
+        .chain(
+            step.env
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.clone())),
+        )
+        .collect::<Vec<_>>();
+    let mut cmd = judge_env.command(&step.command, &extra_env)?;
+    cmd.envs(&step.env);
+    trace!("build step {}: {:?}", &step.name, &cmd);
+    let timeout_millis = step
+        .timeout_millis
+        .map_or(COMPILE_TIMEOUT_MILLIS, |timeout| timeout as u128);
+    let output = launch_program_without_input(cmd, timeout_millis, &judge_env.cancel).await?;
+    match output.termination {
+        ProgramTermination::TimeLimitExceeded
+        | ProgramTermination::MemoryLimitExceeded
+        | ProgramTermination::OutputLimitExceeded => {
+            return Ok(Some(Verdict::CompileTimeLimitExceeded))
+        }
+        ProgramTermination::Cancelled => return Ok(Some(Verdict::Cancelled)),
+        ProgramTermination::Exited | ProgramTermination::SecurityViolation => {}
+    }
+    if output.exit_code != 0 {
+        let diagnostics = diagnostic::parse_diagnostics(
+            &format!("{}\n{}", &output.stdout, &output.stderr),
+            source_file_name,
+        );
+        return Ok(Some(Verdict::CompileError {
+            build_step: step.name.clone(),
+            diagnostics,
+            compiler_stdout: output.stdout,
+            compiler_stderr: output.stderr,
+            compiler_exit_code: output.exit_code,
+        }));
+    }
+    for artifact in &step.artifacts {
+        if !tokio::fs::try_exists(judge_env.work_dir.join(artifact)).await? {
+            return Ok(Some(Verdict::CompileError {
+                build_step: step.name.clone(),
+                diagnostics: vec![],
+                compiler_stdout: output.stdout,
+                compiler_stderr: format!(
+                    "{}\n{} is not produced by the build step {}",
+                    output.stderr, artifact, &step.name
+                ),
+                compiler_exit_code: output.exit_code,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Compile the source code in the working directory by running the build steps of the language.
+/// The compilation is skipped if the same source was compiled successfully there before,
+/// or if it is found in the compile cache.
+/// Returns the verdict when the compilation fails.
+pub async fn compile(judge_env: &JudgeEnv, submission: &Submission) -> Result<Option<Verdict>> {
+    tokio::fs::create_dir_all(&judge_env.work_dir).await?;
+    let language = &submission.language;
+    let hash = compile_key(judge_env, submission);
+    let stamp_file = judge_env.work_dir.join(COMPILE_STAMP_FILENAME);
+    if tokio::fs::read_to_string(&stamp_file).await.ok().as_deref() == Some(hash.as_str()) {
+        trace!("source in {:?} is already compiled", &judge_env.work_dir);
+        return Ok(None);
+    }
+    let _ = tokio::fs::remove_file(&stamp_file).await;
+    if let Some(cache) = &judge_env.compile_cache {
+        if cache.restore(&hash, &judge_env.work_dir).await? {
+            tokio::fs::write(&stamp_file, hash).await?;
+            return Ok(None);
+        }
+    }
+    let mut before = cache::snapshot(&judge_env.work_dir).await?;
+
+    let source_file_name = source_file_name(language);
+    let source_file = judge_env.work_dir.join(&source_file_name);
+    // the source is always stored, interpreted languages run it directly
+    before.remove(&source_file);
+    tokio::fs::write(&source_file, &submission.source).await?;
+    for step in build_steps(language) {
+        if let Some(verdict) =
+            run_build_step(judge_env, &step, &source_file, &source_file_name).await?
+        {
+            return Ok(Some(verdict));
+        }
+    }
+    if let Some(cache) = &judge_env.compile_cache {
+        // a broken cache only costs a compilation next time
+        if let Err(e) = cache.store(&hash, &judge_env.work_dir, &before).await {
+            warn!("failed to store {} into the compile cache: {}", &hash, e);
+        }
+    }
+    tokio::fs::write(&stamp_file, hash).await?;
+    Ok(None)
+}
+
+/// Hooks only prepare or clean up the environment, a failed hook does not affect the verdict
+async fn run_hook(judge_env: &JudgeEnv, commands: &str) -> Result<()> {
+    let cmd = judge_env.command(commands, &[])?;
+    let output = launch_program_without_input(cmd, HOOK_TIMEOUT_MILLIS, &judge_env.cancel).await?;
+    if output.termination != ProgramTermination::Exited || output.exit_code != 0 {
+        warn!(
+            "hook {} failed with exit code {}: {}",
+            commands, output.exit_code, output.stderr
+        );
+    }
+    Ok(())
+}
+
+/// The command running the compiled solution, in the sandbox if the language asks for it
+fn solution_command(judge_env: &JudgeEnv, language: &AdvLanguageItem) -> Result<Command> {
+    let mut cmd = judge_env.command(&language.cmd_run, &[])?;
+    if sanitizer::is_sanitized(&language.cmd_compile) {
+        let user_options = std::env::var(sanitizer::ASAN_OPTIONS).ok();
+        cmd.env(
+            sanitizer::ASAN_OPTIONS,
+            sanitizer::asan_options(user_options.as_deref()),
+        );
+    }
+    if language.sandbox {
+        sandbox::apply(&mut cmd, &judge_env.work_dir)?;
+    }
+    Ok(cmd)
+}
+
+/// Run the compiled solution with `input_file` redirected to its stdin,
+/// together with the before and after run hooks of the language
+pub async fn run<C1: FnMut(&str), C2: FnMut(&str)>(
+    judge_env: &JudgeEnv,
+    language: &AdvLanguageItem,
+    input_file: &Path,
+    output_file: &Path,
+    limit: RunLimit,
+    stdout_line_callback: C1,
+    stderr_line_callback: C2,
+) -> Result<ProgramOutput> {
+    if let Some(hook) = &language.cmd_before_run {
+        run_hook(judge_env, hook).await?;
+    }
+    let cmd = solution_command(judge_env, language)?;
+    trace!("run: {:?}", &cmd);
+    let output = launch_program(
+        cmd,
+        input_file,
+        output_file,
+        limit,
+        &judge_env.cancel,
+        stdout_line_callback,
+        stderr_line_callback,
+    )
+    .await?;
+    if let Some(hook) = &language.cmd_after_run {
+        run_hook(judge_env, hook).await?;
+    }
+    Ok(output)
+}
+
+/// Run a testlib compatible checker: `checker <input> <output> <answer> <result> -appes`.
+/// The outcome written to `result_file` is returned along with the output of the checker,
+/// checkers which do not write it are judged by their exit code.
+pub async fn check(
+    judge_env: &JudgeEnv,
+    checker: &CheckerProgram,
+    input_file: &Path,
+    output_file: &Path,
+    answer_file: &Path,
+    result_file: &Path,
+) -> Result<(ProgramSimpleOutput, Option<TestlibResult>)> {
+    remove_stale_file(result_file).await?;
+    let mut cmd = match checker {
+        CheckerProgram::Bundled(path) => {
+            let mut cmd = Command::new(path);
+            cmd.current_dir(&judge_env.work_dir);
+            cmd
+        }
+        CheckerProgram::Custom {
+            checker_env,
+            checker,
+        } => checker_env.command(&checker.language.cmd_run, &[])?,
+    };
+    cmd.arg(input_file)
+        .arg(output_file)
+        .arg(answer_file)
+        .arg(result_file)
+        .arg(TESTLIB_APPES_MODE);
+    trace!("check: {:?}", &cmd);
+    let output =
+        launch_program_without_input(cmd, CHECKER_TIMEOUT_MILLIS, &judge_env.cancel).await?;
+    Ok((output, read_testlib_result(result_file).await))
+}
+
+/// Makes testlib write the outcome as xml to the result file
+const TESTLIB_APPES_MODE: &str = "-appes";
+
+async fn remove_stale_file(path: &Path) -> Result<()> {
+    if path.exists() {
+        tokio::fs::remove_file(path).await?;
+    }
+    Ok(())
+}
+
+async fn read_testlib_result(result_file: &Path) -> Option<TestlibResult> {
+    // testlib writes the result in windows-1251, the xml itself is ascii
+    let content = tokio::fs::read(result_file).await.ok()?;
+    TestlibResult::parse(&String::from_utf8_lossy(&content))
+}
+
+/// Run the compiled solution in its own directory `run_dir`, where the input is placed under the
+/// required file name. The content of the named output file is copied to `output_file` afterwards,
+/// a missing output file is treated as empty. The output limit applies to the named output file
+/// instead of the stdout, both while the solution runs and after it exits.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_with_file_io<C1: FnMut(&str), C2: FnMut(&str)>(
+    judge_env: &JudgeEnv,
+    language: &AdvLanguageItem,
+    io: &FileIo,
+    run_dir: PathBuf,
+    input_file: &Path,
+    output_file: &Path,
+    limit: RunLimit,
+    stdout_line_callback: C1,
+    stderr_line_callback: C2,
+) -> Result<ProgramOutput> {
+    // files left by the last run must not be mistaken for the output of this one
+    if run_dir.exists() {
+        tokio::fs::remove_dir_all(&run_dir).await?;
+    }
+    tokio::fs::create_dir_all(&run_dir).await?;
+    if let Some(name) = &io.input_file {
+        tokio::fs::copy(input_file, run_dir.join(plain_file_name(name)?)).await?;
+    }
+    let run_env = judge_env.in_dir(run_dir.clone());
+    let Some(name) = &io.output_file else {
+        return run(
+            &run_env,
+            language,
+            input_file,
+            output_file,
+            limit,
+            stdout_line_callback,
+            stderr_line_callback,
+        )
+        .await;
+    };
+    let named_output_file = run_dir.join(plain_file_name(name)?);
+    let stdout_file = output_file.with_extension("stdout");
+    let output_limit = limit.output_limit_bytes.unwrap_or(u64::MAX);
+    // the stdout is not the output here, so the size of the file is watched instead,
+    // the program is killed through a token of its own once the file grows past the limit
+    let run_cancel = CancelToken::new();
+    let run_env = run_env.with_cancel(run_cancel.clone());
+    let running = run(
+        &run_env,
+        language,
+        input_file,
+        &stdout_file,
+        limit,
+        stdout_line_callback,
+        stderr_line_callback,
+    );
+    tokio::pin!(running);
+    let mut watchdog = tokio::time::interval(WATCHDOG_INTERVAL);
+    let mut is_output_exceeded = false;
+    let output = loop {
+        tokio::select! {
+            output = &mut running => break output?,
+            _ = watchdog.tick(), if !run_cancel.is_cancelled() => {
+                if judge_env.cancel.is_cancelled() {
+                    run_cancel.cancel();
+                    continue;
+                }
+                let size = tokio::fs::metadata(&named_output_file)
+                    .await
+                    .map_or(0, |metadata| metadata.len());
+                if size > output_limit {
+                    trace!("{:?} exceeds the output limit! killing the solution", &named_output_file);
+                    is_output_exceeded = true;
+                    run_cancel.cancel();
+                }
+            }
+        }
+    };
+    match tokio::fs::File::open(&named_output_file).await {
+        Ok(file) => {
+            // the size is checked again, the file may grow past the limit between two checks
+            is_output_exceeded |= file.metadata().await?.len() > output_limit;
+            // keep the output up to the limit for inspection
+            let mut writer = tokio::fs::File::create(output_file).await?;
+            tokio::io::copy(&mut file.take(output_limit), &mut writer).await?;
+            writer.flush().await?;
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            trace!("{:?} is not created by the solution", &named_output_file);
+            tokio::fs::write(output_file, "").await?;
+        }
+        Err(e) => return Err(e.into()),
+    }
+    let termination = match output.termination() {
+        ProgramTermination::Exited | ProgramTermination::Cancelled
+            if is_output_exceeded && !judge_env.cancel.is_cancelled() =>
+        {
+            ProgramTermination::OutputLimitExceeded
+        }
+        termination => termination,
+    };
+    read_program_output(
+        output_file,
+        output.exit_code(),
+        termination,
+        output.usage(),
+        output.time_limit_millis(),
+    )
+    .await
+}
+
+/// Run the compiled solution against the compiled interactor:
+/// `interactor <input> <output> <answer> <result> -appes`,
+/// where the interactor writes its own output to `output_file` and its outcome to `result_file`.
+/// The transcript of the interaction is saved as the output of the solution.
+#[allow(clippy::too_many_arguments)]
+pub async fn interact<C1: FnMut(&str), C2: FnMut(&str)>(
+    judge_env: &JudgeEnv,
+    language: &AdvLanguageItem,
+    interactor_env: &JudgeEnv,
+    interactor: &AdvLanguageItem,
+    testcase_files: [&Path; 4],
+    transcript_file: &Path,
+    limit: RunLimit,
+    stdout_line_callback: C1,
+    stderr_line_callback: C2,
+) -> Result<InteractiveOutput> {
+    remove_stale_file(testcase_files[3]).await?;
+    if let Some(hook) = &language.cmd_before_run {
+        run_hook(judge_env, hook).await?;
+    }
+    let solution_cmd = solution_command(judge_env, language)?;
+    let mut interactor_cmd = interactor_env.command(&interactor.cmd_run, &[])?;
+    interactor_cmd.args(testcase_files).arg(TESTLIB_APPES_MODE);
+    trace!("interact: {:?} with {:?}", &solution_cmd, &interactor_cmd);
+    let output = launch_interactive(
+        solution_cmd,
+        interactor_cmd,
+        transcript_file,
+        limit,
+        &judge_env.cancel,
+        stdout_line_callback,
+        stderr_line_callback,
+    )
+    .await?;
+    if let Some(hook) = &language.cmd_after_run {
+        run_hook(judge_env, hook).await?;
+    }
+    Ok(output)
+}
+
+/// Run the compiled solution on the input of a testcase through stdio or the named files
+#[allow(clippy::too_many_arguments)]
+async fn run_testcase<C1: FnMut(&str), C2: FnMut(&str)>(
+    judge_env: &JudgeEnv,
+    language: &AdvLanguageItem,
+    io: &FileIo,
+    testcase_id: &str,
+    input_file: &Path,
+    output_file: &Path,
+    limit: RunLimit,
+    stdout_line_callback: C1,
+    stderr_line_callback: C2,
+) -> Result<ProgramOutput> {
+    if io.is_stdio() {
+        run(
+            judge_env,
+            language,
+            input_file,
+            output_file,
+            limit,
+            stdout_line_callback,
+            stderr_line_callback,
+        )
+        .await
+    } else {
+        run_with_file_io(
+            judge_env,
+            language,
+            io,
+            judge_env.work_dir.join(format!("case-{}", testcase_id)),
+            input_file,
+            output_file,
+            limit,
+            stdout_line_callback,
+            stderr_line_callback,
+        )
+        .await
+    }
+}
+
+/// Judge the solution against a single testcase
+pub async fn judge<C1: FnMut(&str), C2: FnMut(&str)>(
+    judge_env: &JudgeEnv,
+    submission: &Submission,
+    testcase: &TestcaseData,
+    mode: &JudgeMode,
+    limit: RunLimit,
+    stdout_line_callback: C1,
+    stderr_line_callback: C2,
+) -> Result<Verdict> {
+    if let Some(verdict) = compile(judge_env, submission).await? {
+        return Ok(verdict);
+    }
+    if let Some(verdict) = mode.prepare().await? {
+        return Ok(verdict);
+    }
+    let verdict = judge_compiled(
+        judge_env,
+        &submission.language,
+        testcase,
+        mode,
+        limit,
+        stdout_line_callback,
+        stderr_line_callback,
+    )
+    .await?;
+    Ok(attach_backtrace(judge_env, submission, testcase, mode, limit, verdict).await)
+}
+
+fn should_capture_backtrace(judge_env: &JudgeEnv, mode: &JudgeMode, verdict: &Verdict) -> bool {
+    judge_env.capture_backtrace
+        && matches!(mode, JudgeMode::Standard { .. })
+        && matches!(
+            verdict,
+            Verdict::RuntimeError {
+                backtrace: None,
+                ..
+            }
+        )
+}
+
+/// Capture the backtrace of a runtime error if enabled, a failed capture leaves the verdict as it is
+async fn attach_backtrace(
+    judge_env: &JudgeEnv,
+    submission: &Submission,
+    testcase: &TestcaseData,
+    mode: &JudgeMode,
+    limit: RunLimit,
+    verdict: Verdict,
+) -> Verdict {
+    match (mode, verdict) {
+        (
+            JudgeMode::Standard { io, .. },
+            Verdict::RuntimeError {
+                output,
+                sanitizer,
+                backtrace: None,
+            },
+        ) if judge_env.capture_backtrace => {
+            let backtrace = backtrace::capture(judge_env, submission, testcase, io, limit)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!(
+                        "failed to capture the backtrace of testcase {}: {}",
+                        &testcase.id, e
+                    );
+                    None
+                });
+            Verdict::RuntimeError {
+                output,
+                sanitizer,
+                backtrace,
+            }
+        }
+        (_, verdict) => verdict,
+    }
+}
+
+/// Judge a solution which is already compiled in the working directory,
+/// the interactor must be compiled as well in interactive mode.
+/// `limit` holds the time limit of the problem, which is adjusted for the language of the solution.
+pub async fn judge_compiled<C1: FnMut(&str), C2: FnMut(&str)>(
+    judge_env: &JudgeEnv,
+    language: &AdvLanguageItem,
+    testcase: &TestcaseData,
+    mode: &JudgeMode,
+    limit: RunLimit,
+    stdout_line_callback: C1,
+    mut stderr_line_callback: C2,
+) -> Result<Verdict> {
+    if judge_env.cancel.is_cancelled() {
+        return Ok(Verdict::Cancelled);
+    }
+    let limit = RunLimit {
+        timeout_millis: language.effective_time_limit_millis(limit.timeout_millis),
+        // a sanitizer reserves terabytes of address space and inflates the memory usage,
+        // so the memory limit is not enforced on such a build
+        memory_limit_kib: limit
+            .memory_limit_kib
+            .filter(|_| !sanitizer::is_sanitized(&language.cmd_compile)),
+        ..limit
+    };
+    let mut stderr = String::new();
+    let keep_stderr_line = |line: &str| {
+        if stderr.len() < KEPT_STDERR_BYTES {
+            stderr.push_str(line);
+            stderr.push('\n');
+        }
+        stderr_line_callback(line);
+    };
+    let input_file = judge_env.work_dir.join(format!("case-{}.in", &testcase.id));
+    let answer_file = judge_env
+        .work_dir
+        .join(format!("case-{}.ans", &testcase.id));
+    let output_file = judge_env
+        .work_dir
+        .join(format!("case-{}.out", &testcase.id));
+    let result_file = judge_env
+        .work_dir
+        .join(format!("case-{}.result.xml", &testcase.id));
+    tokio::fs::write(&input_file, &testcase.input).await?;
+    tokio::fs::write(&answer_file, &testcase.answer).await?;
+
+    let (checker, io) = match mode {
+        JudgeMode::Standard { checker, io } => (checker, io),
+        JudgeMode::Interactive {
+            interactor_env,
+            interactor,
+        } => {
+            let transcript_file = judge_env
+                .work_dir
+                .join(format!("case-{}.transcript", &testcase.id));
+            let InteractiveOutput {
+                solution,
+                interactor,
+            } = interact(
+                judge_env,
+                language,
+                interactor_env,
+                &interactor.language,
+                [&input_file, &output_file, &answer_file, &result_file],
+                &transcript_file,
+                limit,
+                stdout_line_callback,
+                keep_stderr_line,
+            )
+            .await?;
+            let result = read_testlib_result(&result_file).await;
+            let sanitizer = sanitizer::parse_sanitizer_report(&stderr, &source_file_name(language))
+                .map(Box::new);
+            return Ok(judge_interaction(solution, interactor, result, sanitizer));
+        }
+    };
+    let output = run_testcase(
+        judge_env,
+        language,
+        io,
+        &testcase.id,
+        &input_file,
+        &output_file,
+        limit,
+        stdout_line_callback,
+        keep_stderr_line,
+    )
+    .await?;
+    match output.termination() {
+        ProgramTermination::TimeLimitExceeded => return Ok(Verdict::TimeLimitExceeded { output }),
+        ProgramTermination::MemoryLimitExceeded => {
+            return Ok(Verdict::MemoryLimitExceeded { output })
+        }
+        ProgramTermination::OutputLimitExceeded => {
+            return Ok(Verdict::OutputLimitExceeded { output })
+        }
+        ProgramTermination::SecurityViolation => return Ok(Verdict::SecurityViolation { output }),
+        ProgramTermination::Cancelled => return Ok(Verdict::Cancelled),
+        ProgramTermination::Exited => {}
+    }
+    if output.exit_code() != 0 {
+        return Ok(Verdict::RuntimeError {
+            output,
+            sanitizer: sanitizer::parse_sanitizer_report(&stderr, &source_file_name(language))
+                .map(Box::new),
+            backtrace: None,
+        });
+    }
+
+    let (checker_output, result) = check(
+        judge_env,
+        checker,
+        &input_file,
+        &output_file,
+        &answer_file,
+        &result_file,
+    )
+    .await?;
+    Ok(Verdict::from_checker(output, checker_output, result))
+}
+
+fn judge_interaction(
+    output: ProgramOutput,
+    interactor: ProgramSimpleOutput,
+    result: Option<TestlibResult>,
+    sanitizer: Option<Box<SanitizerReport>>,
+) -> Verdict {
+    match output.termination() {
+        ProgramTermination::TimeLimitExceeded => return Verdict::TimeLimitExceeded { output },
+        ProgramTermination::MemoryLimitExceeded => return Verdict::MemoryLimitExceeded { output },
+        ProgramTermination::OutputLimitExceeded => return Verdict::OutputLimitExceeded { output },
+        ProgramTermination::SecurityViolation => return Verdict::SecurityViolation { output },
+        ProgramTermination::Cancelled => return Verdict::Cancelled,
+        ProgramTermination::Exited => {}
+    }
+    // the solution is usually killed by a broken pipe once the interactor gives up,
+    // so a failed interactor takes precedence over the runtime error
+    if interactor.termination == ProgramTermination::Exited
+        && interactor.exit_code == 0
+        && output.exit_code() != 0
+    {
+        return Verdict::RuntimeError {
+            output,
+            sanitizer,
+            backtrace: None,
+        };
+    }
+    Verdict::from_checker(output, interactor, result)
+}
+
+/// Progress of a testcase reported by [`judge_all`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "type")]
+pub enum JudgeProgress {
+    Stdout { line: String },
+    Stderr { line: String },
+    Judged { verdict: Verdict },
+    Failed { error: String },
+}
+
+/// Compile the solution once, then judge every testcase with at most `concurrency` of them
+/// running at the same time. `on_progress` receives the id of the testcase along with its progress,
+/// every testcase ends with either `Judged` or `Failed`.
+/// If the compilation fails, all testcases are judged with the compile verdict.
+/// Backtraces of runtime errors are captured one at a time once every testcase is run,
+/// so those testcases are reported as judged last.
+pub async fn judge_all<F: Fn(&str, JudgeProgress) + Send + Sync + 'static>(
+    judge_env: &JudgeEnv,
+    submission: &Submission,
+    testcases: Vec<TestcaseData>,
+    mode: &JudgeMode,
+    limit: RunLimit,
+    concurrency: usize,
+    on_progress: F,
+) -> Result<()> {
+    let verdict = match compile(judge_env, submission).await? {
+        None => mode.prepare().await?,
+        verdict => verdict,
+    };
+    if let Some(verdict) = verdict {
+        for testcase in testcases {
+            on_progress(
+                &testcase.id,
+                JudgeProgress::Judged {
+                    verdict: verdict.clone(),
+                },
+            );
+        }
+        return Ok(());
+    }
+
+    let on_progress = Arc::new(on_progress);
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+    for testcase in testcases {
+        let judge_env = judge_env.clone();
+        let language = submission.language.clone();
+        let mode = mode.clone();
+        let semaphore = semaphore.clone();
+        let on_progress = on_progress.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await?;
+            trace!(
+                "judge testcase {} in {:?}",
+                &testcase.id,
+                &judge_env.work_dir
+            );
+            let (stdout_progress, stderr_progress) = (on_progress.clone(), on_progress.clone());
+            let (stdout_id, stderr_id) = (testcase.id.clone(), testcase.id.clone());
+            let result = judge_compiled(
+                &judge_env,
+                &language,
+                &testcase,
+                &mode,
+                limit,
+                move |line| {
+                    stdout_progress(
+                        &stdout_id,
+                        JudgeProgress::Stdout {
+                            line: line.to_string(),
+                        },
+                    )
+                },
+                move |line| {
+                    stderr_progress(
+                        &stderr_id,
+                        JudgeProgress::Stderr {
+                            line: line.to_string(),
+                        },
+                    )
+                },
+            )
+            .await;
+            let progress = match result {
+                Ok(verdict) if should_capture_backtrace(&judge_env, &mode, &verdict) => {
+                    return anyhow::Ok(Some((testcase, verdict)));
+                }
+                Ok(verdict) => JudgeProgress::Judged { verdict },
+                Err(e) => JudgeProgress::Failed {
+                    error: e.to_string(),
+                },
+            };
+            on_progress(&testcase.id, progress);
+            anyhow::Ok(None)
+        });
+    }
+    let mut crashed = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        crashed.extend(result??);
+    }
+    for (testcase, verdict) in crashed {
+        let verdict =
+            attach_backtrace(judge_env, submission, &testcase, mode, limit, verdict).await;
+        on_progress(&testcase.id, JudgeProgress::Judged { verdict });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    fn shell_language(cmd_run: &str) -> AdvLanguageItem {
+        serde_json::from_value(serde_json::json!({
+            "base": "Text",
+            "cmd_compile": "",
+            "cmd_before_run": null,
+            "cmd_after_run": null,
+            "cmd_run": cmd_run,
+            "lsp": null,
+            "lsp_connect": null,
+            "initial_solution_content": null,
+            "sandbox": false,
+            "time_multiplier": 1.0,
+        }))
+        .unwrap()
+    }
+
+    /// Run a shell command writing its output to `a.out` with an output limit of 1000 bytes
+    #[cfg(unix)]
+    async fn run_with_named_output(cmd_run: &str) -> ProgramOutput {
+        let work_dir =
+            std::env::temp_dir().join(format!("algorimejo-judge-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&work_dir).await.unwrap();
+        let input_file = work_dir.join("case.in");
+        tokio::fs::write(&input_file, "").await.unwrap();
+        let judge_env = JudgeEnv::new(work_dir.clone(), HashMap::new(), CancelToken::new());
+        let io = FileIo {
+            input_file: None,
+            output_file: Some("a.out".to_string()),
+        };
+        let output = run_with_file_io(
+            &judge_env,
+            &shell_language(cmd_run),
+            &io,
+            work_dir.join("run"),
+            &input_file,
+            &work_dir.join("case.out"),
+            RunLimit {
+                timeout_millis: 5000,
+                memory_limit_kib: None,
+                output_limit_bytes: Some(1000),
+            },
+            |_| {},
+            |_| {},
+        )
+        .await
+        .unwrap();
+        let _ = std::fs::remove_dir_all(&work_dir);
+        output
+    }
+
+    #[test]
+    fn test_plain_file_name() {
+        assert_eq!(plain_file_name("a.out").unwrap(), "a.out");
+        assert!(plain_file_name("../x").is_err());
+        assert!(plain_file_name("dir/x").is_err());
+        assert!(plain_file_name("/tmp/x").is_err());
+        assert!(plain_file_name("..").is_err());
+        assert!(plain_file_name("").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_named_output_file_limit() {
+        let output = run_with_named_output("sh -c 'echo 1 > a.out'").await;
+        assert_eq!(output.termination(), ProgramTermination::Exited);
+
+        // killed while running, otherwise it would write until the timeout
+        let output = run_with_named_output("sh -c 'yes > a.out'").await;
+        assert_eq!(
+            output.termination(),
+            ProgramTermination::OutputLimitExceeded
+        );
+        assert!(output.usage().wall_time_millis < 5000);
+
+        // exits before the watchdog notices
+        let output = run_with_named_output("sh -c 'head -c 5000 /dev/zero > a.out'").await;
+        assert_eq!(
+            output.termination(),
+            ProgramTermination::OutputLimitExceeded
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! Parse the report a sanitizer prints to stderr when a solution built with `-fsanitize` crashes.
+//!
+//! AddressSanitizer and the other sanitizers of the same family print a header and a stack trace:
+//!
+//! ```text
+//! ==4242==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000014 at pc 0x5581 bp 0x7ffd sp 0x7ffd
+//! READ of size 4 at 0x602000000014 thread T0
+//!     #0 0x5581 in solve(int) /tmp/algorime-tt-1/code.cpp:5:12
+//!     #1 0x55a0 in main /tmp/algorime-tt-1/code.cpp:9:5
+//! ```
+//!
+//! UndefinedBehaviorSanitizer prints a single line in the style of a compiler diagnostic:
+//!
+//! ```text
+//! /tmp/algorime-tt-1/code.cpp:4:7: runtime error: signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'
+//! ```
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::judge::diagnostic::{is_source_file, parse_location};
+
+const UNDEFINED_BEHAVIOR_SANITIZER: &str = "UndefinedBehaviorSanitizer";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct SanitizerReport {
+    /// Like `AddressSanitizer`
+    pub sanitizer: String,
+    pub message: String,
+    /// Where it is reported in the source of the solution, 1-based
+    pub line: Option<u32>,
+    /// 1-based
+    pub column: Option<u32>,
+}
+
+/// Whether the solution is built with a sanitizer by `cmd_compile`
+pub fn is_sanitized(cmd_compile: &str) -> bool {
+    cmd_compile.contains("-fsanitize=")
+}
+
+pub const ASAN_OPTIONS: &str = "ASAN_OPTIONS";
+
+/// `ASAN_OPTIONS` a sanitized solution is run with. A leak is harmless in a solution,
+/// but LeakSanitizer would make it exit with an error, so leak detection is off
+/// unless the options given by the user turn it on again.
+pub fn asan_options(user_options: Option<&str>) -> String {
+    match user_options {
+        Some(options) if !options.is_empty() => format!("detect_leaks=0:{}", options),
+        _ => "detect_leaks=0".to_string(),
+    }
+}
+
+/// `==4242==ERROR: AddressSanitizer: message` or `WARNING: ThreadSanitizer: message`
+fn parse_header(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start_matches(|c: char| c == '=' || c.is_ascii_digit());
+    let rest = line
+        .strip_prefix("ERROR: ")
+        .or_else(|| line.strip_prefix("WARNING: "))?;
+    let (sanitizer, message) = rest.split_once(": ")?;
+    sanitizer
+        .ends_with("Sanitizer")
+        .then_some((sanitizer, message.trim()))
+}
+
+/// `#0 0x5581 in solve(int) /tmp/code.cpp:5:12`, the location is the last part
+fn parse_frame_location(line: &str) -> Option<(&str, u32, Option<u32>)> {
+    let rest = line.trim().strip_prefix('#')?;
+    let (_, location) = rest.split_once(" in ")?.1.rsplit_once(' ')?;
+    parse_location(location)
+}
+
+/// Parse the first report in the stderr of a solution, its location is the innermost frame in a file
+/// named `source_file_name`. Returns `None` if there is no report.
+pub fn parse_sanitizer_report(stderr: &str, source_file_name: &str) -> Option<SanitizerReport> {
+    let lines = stderr.lines().collect::<Vec<_>>();
+    for (i, line) in lines.iter().enumerate() {
+        if let Some((location, message)) = line.split_once(": runtime error: ") {
+            let (line, column) = parse_location(location)
+                .filter(|(file, _, _)| is_source_file(file, source_file_name))
+                .map_or((None, None), |(_, line, column)| (Some(line), column));
+            return Some(SanitizerReport {
+                sanitizer: UNDEFINED_BEHAVIOR_SANITIZER.to_string(),
+                message: message.trim().to_string(),
+                line,
+                column,
+            });
+        }
+        if let Some((sanitizer, message)) = parse_header(line) {
+            let location = lines[i + 1..]
+                .iter()
+                .filter_map(|line| parse_frame_location(line))
+                .find(|(file, _, _)| is_source_file(file, source_file_name));
+            return Some(SanitizerReport {
+                sanitizer: sanitizer.to_string(),
+                message: message.to_string(),
+                line: location.map(|(_, line, _)| line),
+                column: location.and_then(|(_, _, column)| column),
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_address_sanitizer_report() {
+        let stderr = r#"=================================================================
+==4242==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000014 at pc 0x5581 bp 0x7ffd sp 0x7ffd
+READ of size 4 at 0x602000000014 thread T0
+    #0 0x7f12 in __interceptor_memcpy (/lib/x86_64-linux-gnu/libasan.so.6+0x3a6b3)
+    #1 0x5581 in solve(int, std::vector<int, std::allocator<int> >&) /tmp/algorime-tt-1/code.cpp:5:12
+    #2 0x55a0 in main /tmp/algorime-tt-1/code.cpp:9:5
+
+SUMMARY: AddressSanitizer: heap-buffer-overflow /tmp/algorime-tt-1/code.cpp:5 in solve(int)
+"#;
+        assert_eq!(
+            parse_sanitizer_report(stderr, "code.cpp"),
+            Some(SanitizerReport {
+                sanitizer: "AddressSanitizer".to_string(),
+                message: "heap-buffer-overflow on address 0x602000000014 at pc 0x5581 bp 0x7ffd sp 0x7ffd".to_string(),
+                line: Some(5),
+                column: Some(12),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_undefined_behavior_sanitizer_report() {
+        let stderr = "/tmp/algorime-tt-1/code.cpp:4:7: runtime error: signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'\n";
+        assert_eq!(
+            parse_sanitizer_report(stderr, "code.cpp"),
+            Some(SanitizerReport {
+                sanitizer: "UndefinedBehaviorSanitizer".to_string(),
+                message:
+                    "signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'"
+                        .to_string(),
+                line: Some(4),
+                column: Some(7),
+            })
+        );
+        assert_eq!(
+            parse_sanitizer_report("Segmentation fault\n", "code.cpp"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_leak_sanitizer_report() {
+        let stderr = r#"
+=================================================================
+==4242==ERROR: LeakSanitizer: detected memory leaks
+
+Direct leak of 40 byte(s) in 1 object(s) allocated from:
+    #0 0x7f12 in operator new[](unsigned long) ../../../../src/libsanitizer/asan/asan_new_delete.cpp:98
+    #1 0x5581 in main /tmp/algorime-tt-1/code.cpp:3:18
+    #2 0x7f13 in __libc_start_main (/lib/x86_64-linux-gnu/libc.so.6+0x29d8f)
+
+SUMMARY: AddressSanitizer: 40 byte(s) leaked in 1 allocation(s).
+"#;
+        assert_eq!(
+            parse_sanitizer_report(stderr, "code.cpp"),
+            Some(SanitizerReport {
+                sanitizer: "LeakSanitizer".to_string(),
+                message: "detected memory leaks".to_string(),
+                line: Some(3),
+                column: Some(18),
+            })
+        );
+    }
+
+    #[test]
+    fn test_asan_options() {
+        assert_eq!(asan_options(None), "detect_leaks=0");
+        assert_eq!(asan_options(Some("")), "detect_leaks=0");
+        // a later option wins, so the user can still turn leak detection on
+        assert_eq!(
+            asan_options(Some("detect_leaks=1")),
+            "detect_leaks=0:detect_leaks=1"
+        );
+    }
+}
@@ -0,0 +1,321 @@
+//! Stress testing: generate a random input with the seed as the only argument of the generator,
+//! take the output of the reference solution as the answer, then judge the target solution.
+//! Repeat with the next seed until the target fails or the budget runs out.
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tokio::time::Instant;
+
+use crate::{
+    judge::{
+        compile_tool, judge_compiled, run_testcase, FileIo, JudgeEnv, JudgeMode, Submission,
+        TestcaseData, Verdict,
+    },
+    runner::run::{launch_program, ProgramOutput, ProgramTermination, RunLimit},
+};
+
+/// The generator and the reference solution are not expected to be fast,
+/// they are only stopped if they take longer than this
+const HELPER_TIMEOUT_MILLIS: u128 = 12000;
+
+/// A program taking part in the stress test, compiled in its own working directory
+#[derive(Debug, Clone)]
+pub struct StressProgram {
+    pub env: JudgeEnv,
+    pub submission: Submission,
+}
+
+#[derive(Debug, Clone)]
+pub struct StressTask {
+    pub generator: StressProgram,
+    pub reference: StressProgram,
+    pub target: StressProgram,
+    /// Only standard problems are supported, the reference solution uses the same I/O files
+    pub mode: JudgeMode,
+    /// Limit of the target solution
+    pub limit: RunLimit,
+    pub first_seed: u64,
+    pub max_iterations: Option<u64>,
+    pub time_budget_millis: u128,
+}
+
+/// Reported after every iteration passed by the target solution
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct StressProgress {
+    pub iteration: u64,
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "type")]
+pub enum StressOutcome {
+    /// The target solution is not accepted on the input generated with `seed`
+    Mismatch {
+        seed: u64,
+        input: String,
+        answer: String,
+        verdict: Verdict,
+    },
+    /// The generator did not exit normally
+    GeneratorFailed {
+        seed: u64,
+        output: ProgramOutput,
+    },
+    /// The reference solution did not exit normally, so there is no answer to compare with
+    ReferenceFailed {
+        seed: u64,
+        output: ProgramOutput,
+    },
+    /// Every iteration passed before the budget ran out
+    Passed {
+        iterations: u64,
+    },
+    Cancelled {
+        iterations: u64,
+    },
+}
+
+fn exited_normally(output: &ProgramOutput) -> bool {
+    output.termination() == ProgramTermination::Exited && output.exit_code() == 0
+}
+
+fn helper_limit(limit: RunLimit) -> RunLimit {
+    RunLimit {
+        timeout_millis: HELPER_TIMEOUT_MILLIS,
+        memory_limit_kib: None,
+        output_limit_bytes: limit.output_limit_bytes,
+    }
+}
+
+/// What a helper program printed, it is only trusted if it exits normally
+#[derive(Debug, Clone)]
+pub enum HelperOutput {
+    Printed(String),
+    Failed(ProgramOutput),
+    Cancelled,
+}
+
+impl HelperOutput {
+    async fn read(output: ProgramOutput, output_file: &Path) -> Result<Self> {
+        if output.termination() == ProgramTermination::Cancelled {
+            return Ok(HelperOutput::Cancelled);
+        }
+        if !exited_normally(&output) {
+            return Ok(HelperOutput::Failed(output));
+        }
+        let printed = String::from_utf8_lossy(&tokio::fs::read(output_file).await?).into_owned();
+        Ok(HelperOutput::Printed(printed))
+    }
+}
+
+/// Run the compiled generator with `args` appended to its run command,
+/// what it prints is the input of `testcase_id`
+pub async fn generate_input(
+    generator: &StressProgram,
+    args: &str,
+    testcase_id: &str,
+    limit: RunLimit,
+) -> Result<HelperOutput> {
+    let generator_input = generator.env.work_dir.join("empty.in");
+    let input_file = generator
+        .env
+        .work_dir
+        .join(format!("case-{}.in", testcase_id));
+    tokio::fs::write(&generator_input, "").await?;
+    let cmd = generator.env.command(
+        &format!("{} {}", &generator.submission.language.cmd_run, args),
+        &[],
+    )?;
+    trace!("generate {}: {:?}", testcase_id, &cmd);
+    let output = launch_program(
+        cmd,
+        &generator_input,
+        &input_file,
+        helper_limit(limit),
+        &generator.env.cancel,
+        |_| {},
+        |_| {},
+    )
+    .await?;
+    HelperOutput::read(output, &input_file).await
+}
+
+/// Run the compiled reference solution on `input`, what it prints is the answer of `testcase_id`
+pub async fn generate_answer(
+    reference: &StressProgram,
+    io: &FileIo,
+    testcase_id: &str,
+    input: &str,
+    limit: RunLimit,
+) -> Result<HelperOutput> {
+    let input_file = reference
+        .env
+        .work_dir
+        .join(format!("case-{}.in", testcase_id));
+    let answer_file = reference
+        .env
+        .work_dir
+        .join(format!("case-{}.ans", testcase_id));
+    tokio::fs::write(&input_file, input).await?;
+    let output = run_testcase(
+        &reference.env,
+        &reference.submission.language,
+        io,
+        testcase_id,
+        &input_file,
+        &answer_file,
+        helper_limit(limit),
+        |_| {},
+        |_| {},
+    )
+    .await?;
+    HelperOutput::read(output, &answer_file).await
+}
+
+/// Compile the programs along with the checker, returns false if it is cancelled.
+/// Every program must compile, the name tells which one failed.
+pub async fn compile_programs(
+    programs: &[(&StressProgram, &str)],
+    mode: &JudgeMode,
+) -> Result<bool> {
+    for (program, name) in programs {
+        if compile_tool(&program.env, &program.submission, name)
+            .await?
+            .is_some()
+        {
+            return Ok(false);
+        }
+    }
+    Ok(mode.prepare().await?.is_none())
+}
+
+/// Result of running the target solution and the reference solution on the same input
+#[derive(Debug, Clone)]
+pub enum Comparison {
+    Accepted,
+    Mismatch { answer: String, verdict: Verdict },
+    ReferenceFailed(ProgramOutput),
+    Cancelled,
+}
+
+/// Take the output of the compiled reference solution on `testcase` as the answer,
+/// then judge the compiled target solution with it
+pub async fn compare(
+    reference: &StressProgram,
+    target: &StressProgram,
+    mode: &JudgeMode,
+    limit: RunLimit,
+    testcase_id: &str,
+    input: String,
+) -> Result<Comparison> {
+    let JudgeMode::Standard { io, .. } = mode else {
+        return Err(anyhow!("Only standard problems can be compared"));
+    };
+    let answer = match generate_answer(reference, io, testcase_id, &input, limit).await? {
+        HelperOutput::Printed(answer) => answer,
+        HelperOutput::Failed(output) => return Ok(Comparison::ReferenceFailed(output)),
+        HelperOutput::Cancelled => return Ok(Comparison::Cancelled),
+    };
+
+    let testcase = TestcaseData {
+        id: testcase_id.to_string(),
+        input,
+        answer,
+    };
+    let verdict = judge_compiled(
+        &target.env,
+        &target.submission.language,
+        &testcase,
+        mode,
+        limit,
+        |_| {},
+        |_| {},
+    )
+    .await?;
+    trace!("compare {}: {:?}", testcase_id, &verdict);
+    Ok(match verdict {
+        Verdict::Accepted { .. } => Comparison::Accepted,
+        Verdict::Cancelled => Comparison::Cancelled,
+        verdict => Comparison::Mismatch {
+            answer: testcase.answer,
+            verdict,
+        },
+    })
+}
+
+/// Compile every program once, then stress test the target solution
+pub async fn stress<F: FnMut(StressProgress)>(
+    task: &StressTask,
+    mut on_progress: F,
+) -> Result<StressOutcome> {
+    if !matches!(task.mode, JudgeMode::Standard { .. }) {
+        return Err(anyhow!(
+            "Stress testing interactive problems is not supported"
+        ));
+    }
+    let cancel = &task.target.env.cancel;
+    let programs = [
+        (&task.generator, "generator"),
+        (&task.reference, "reference solution"),
+        (&task.target, "solution"),
+    ];
+    if !compile_programs(&programs, &task.mode).await? {
+        return Ok(StressOutcome::Cancelled { iterations: 0 });
+    }
+    let start_time = Instant::now();
+    let mut iterations = 0;
+    while task.max_iterations.is_none_or(|max| iterations < max)
+        && start_time.elapsed().as_millis() < task.time_budget_millis
+    {
+        if cancel.is_cancelled() {
+            return Ok(StressOutcome::Cancelled { iterations });
+        }
+        let seed = task.first_seed + iterations;
+        let testcase_id = format!("stress-{}", seed);
+
+        let input =
+            match generate_input(&task.generator, &seed.to_string(), &testcase_id, task.limit)
+                .await?
+            {
+                HelperOutput::Printed(input) => input,
+                HelperOutput::Failed(output) => {
+                    return Ok(StressOutcome::GeneratorFailed { seed, output })
+                }
+                HelperOutput::Cancelled => return Ok(StressOutcome::Cancelled { iterations }),
+            };
+
+        match compare(
+            &task.reference,
+            &task.target,
+            &task.mode,
+            task.limit,
+            &testcase_id,
+            input.clone(),
+        )
+        .await?
+        {
+            Comparison::Accepted => {}
+            Comparison::Mismatch { answer, verdict } => {
+                return Ok(StressOutcome::Mismatch {
+                    seed,
+                    input,
+                    answer,
+                    verdict,
+                })
+            }
+            Comparison::ReferenceFailed(output) => {
+                return Ok(StressOutcome::ReferenceFailed { seed, output })
+            }
+            Comparison::Cancelled => return Ok(StressOutcome::Cancelled { iterations }),
+        }
+        iterations += 1;
+        on_progress(StressProgress {
+            iteration: iterations,
+            seed,
+        });
+    }
+    Ok(StressOutcome::Passed { iterations })
+}
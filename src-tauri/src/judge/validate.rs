@@ -0,0 +1,107 @@
+//! Check testcase inputs against the constraints of the problem with a testlib validator,
+//! which reads the input from stdin and exits with a non-zero code if it is invalid.
+use anyhow::Result;
+use log::trace;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    judge::{compile_tool, JudgeEnv, Submission, TestcaseData, Verdict, CHECKER_TIMEOUT_MILLIS},
+    runner::run::{launch_program, ProgramTermination, RunLimit},
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "result")]
+pub enum Validation {
+    #[serde(rename = "VALID")]
+    Valid,
+    /// The validator exited with a non-zero code: the input violates the constraints, the message tells where
+    #[serde(rename = "INVALID")]
+    Invalid { validator_message: String },
+    /// The validator timed out, was killed by a signal or hit a resource limit, so the input could not be checked
+    #[serde(rename = "FAIL")]
+    ValidatorError {
+        termination: ProgramTermination,
+        validator_message: String,
+    },
+    #[serde(rename = "CANCELLED")]
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct TestcaseValidation {
+    pub testcase_id: String,
+    pub validation: Validation,
+}
+
+/// Run the compiled validator over the input of a testcase
+pub async fn validate(
+    validator_env: &JudgeEnv,
+    validator: &Submission,
+    testcase: &TestcaseData,
+) -> Result<Validation> {
+    if validator_env.cancel.is_cancelled() {
+        return Ok(Validation::Cancelled);
+    }
+    let input_file = validator_env
+        .work_dir
+        .join(format!("case-{}.in", &testcase.id));
+    let output_file = validator_env
+        .work_dir
+        .join(format!("case-{}.validator.out", &testcase.id));
+    tokio::fs::write(&input_file, &testcase.input).await?;
+
+    let cmd = validator_env.command(&validator.language.cmd_run, &[])?;
+    trace!("validate: {:?}", &cmd);
+    let mut validator_message = String::new();
+    let output = launch_program(
+        cmd,
+        &input_file,
+        &output_file,
+        RunLimit {
+            timeout_millis: CHECKER_TIMEOUT_MILLIS,
+            memory_limit_kib: None,
+            output_limit_bytes: None,
+        },
+        &validator_env.cancel,
+        |_| {},
+        |line| {
+            validator_message.push_str(line);
+            validator_message.push('\n');
+        },
+    )
+    .await?;
+    Ok(match output.termination() {
+        ProgramTermination::Cancelled => Validation::Cancelled,
+        ProgramTermination::Exited if output.exit_code() == 0 => Validation::Valid,
+        ProgramTermination::Exited => Validation::Invalid { validator_message },
+        termination => Validation::ValidatorError {
+            termination,
+            validator_message,
+        },
+    })
+}
+
+/// Compile the validator once, then validate the input of every testcase in order
+pub async fn validate_all(
+    validator_env: &JudgeEnv,
+    validator: &Submission,
+    testcases: Vec<TestcaseData>,
+) -> Result<Vec<TestcaseValidation>> {
+    let is_cancelled =
+        compile_tool(validator_env, validator, "validator").await? == Some(Verdict::Cancelled);
+    let mut validations = Vec::with_capacity(testcases.len());
+    for testcase in testcases {
+        let validation = if is_cancelled {
+            Validation::Cancelled
+        } else {
+            validate(validator_env, validator, &testcase).await?
+        };
+        trace!("validate testcase {}: {:?}", &testcase.id, &validation);
+        validations.push(TestcaseValidation {
+            testcase_id: testcase.id,
+            validation,
+        });
+    }
+    Ok(validations)
+}
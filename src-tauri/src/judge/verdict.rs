@@ -0,0 +1,535 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    judge::{backtrace::Backtrace, diagnostic::Diagnostic, sanitizer::SanitizerReport},
+    runner::run::{ProgramOutput, ProgramSimpleOutput, ProgramTermination},
+};
+
+/// Final result of judging a solution against a testcase
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "result")]
+pub enum Verdict {
+    #[serde(rename = "CE")]
+    CompileError {
+        /// Name of the failed build step, `compile` for `cmd_compile`
+        build_step: String,
+        compiler_stdout: String,
+        compiler_stderr: String,
+        compiler_exit_code: i32,
+        /// Parsed from the output of the compiler
+        diagnostics: Vec<Diagnostic>,
+    },
+    #[serde(rename = "CETLE")]
+    CompileTimeLimitExceeded,
+    #[serde(rename = "AC")]
+    Accepted {
+        output: ProgramOutput,
+        checker_message: String,
+    },
+    #[serde(rename = "WA")]
+    WrongAnswer {
+        output: ProgramOutput,
+        checker_message: String,
+    },
+    #[serde(rename = "PE")]
+    PresentationError {
+        output: ProgramOutput,
+        checker_message: String,
+    },
+    /// Scored by a points checker such as `pointscmp`
+    #[serde(rename = "PTS")]
+    Points {
+        output: ProgramOutput,
+        checker_message: String,
+        score: f64,
+    },
+    /// Partially correct, the score is the partial type reported by the checker
+    #[serde(rename = "PC")]
+    PartiallyCorrect {
+        output: ProgramOutput,
+        checker_message: String,
+        score: f64,
+    },
+    #[serde(rename = "TLE")]
+    TimeLimitExceeded { output: ProgramOutput },
+    #[serde(rename = "MLE")]
+    MemoryLimitExceeded { output: ProgramOutput },
+    #[serde(rename = "OLE")]
+    OutputLimitExceeded { output: ProgramOutput },
+    /// The solution made a system call forbidden by the sandbox
+    #[serde(rename = "SV")]
+    SecurityViolation { output: ProgramOutput },
+    #[serde(rename = "RE")]
+    RuntimeError {
+        output: ProgramOutput,
+        /// Reported by a sanitizer the solution is built with
+        sanitizer: Option<Box<SanitizerReport>>,
+        /// Captured by running the testcase again under gdb, if enabled
+        backtrace: Option<Backtrace>,
+    },
+    #[serde(rename = "CHKTLE")]
+    CheckerTimeLimitExceeded {
+        output: ProgramOutput,
+        checker_message: String,
+    },
+    #[serde(rename = "CHKRE")]
+    CheckerError {
+        output: ProgramOutput,
+        checker_message: String,
+    },
+    /// The task is cancelled before it is judged
+    #[serde(rename = "CANCELLED")]
+    Cancelled,
+}
+
+/// Outcome of a testlib checker, read from the result file it writes in `-appes` mode:
+///
+/// ```text
+/// <?xml version="1.0" encoding="windows-1251"?><result outcome = "points" points = "12.5">message</result>
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestlibResult {
+    pub outcome: String,
+    pub points: Option<f64>,
+    pub pctype: Option<i32>,
+    pub message: String,
+}
+
+impl TestlibResult {
+    pub fn parse(xml: &str) -> Option<Self> {
+        let start = xml.find("<result")? + "<result".len();
+        let tag_end = start + xml[start..].find('>')?;
+        let content_end = tag_end + xml[tag_end..].find("</result>")?;
+        let attributes = parse_attributes(&xml[start..tag_end]);
+        let attribute = |name: &str| {
+            attributes
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        Some(Self {
+            outcome: attribute("outcome")?.to_string(),
+            points: attribute("points").and_then(|p| p.parse().ok()),
+            pctype: attribute("pctype").and_then(|p| p.parse().ok()),
+            message: unescape_xml(&xml[tag_end + 1..content_end]),
+        })
+    }
+}
+
+/// Parse `key = "value"` pairs, testlib puts spaces around `=`
+fn parse_attributes(s: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut rest = s;
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim().to_string();
+        let value_start = match rest[eq + 1..].find('"') {
+            Some(pos) => eq + 1 + pos + 1,
+            None => break,
+        };
+        let value_end = match rest[value_start..].find('"') {
+            Some(pos) => value_start + pos,
+            None => break,
+        };
+        attributes.push((key, unescape_xml(&rest[value_start..value_end])));
+        rest = &rest[value_end + 1..];
+    }
+    attributes
+}
+
+fn unescape_xml(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Exit codes of testlib
+const POINTS_EXIT_CODE: i32 = 7;
+/// Only a checker built with `TESTSYS` defined adds this base to the partial type,
+/// otherwise the bundled testlib.h defines it as 0 and `_pc(n)` exits with plain n
+const PC_BASE_EXIT_CODE: i32 = 50;
+/// testlib accepts partial types up to 100
+const PC_MAX_EXIT_CODE: i32 = PC_BASE_EXIT_CODE + 100;
+/// Reported by the shell when the checker can not be executed or is not found
+const SHELL_FAILURE_EXIT_CODES: [i32; 2] = [126, 127];
+
+impl Verdict {
+    /// The `result` tag it is serialized with, like `AC`
+    pub fn result(&self) -> &'static str {
+        match self {
+            Verdict::CompileError { .. } => "CE",
+            Verdict::CompileTimeLimitExceeded => "CETLE",
+            Verdict::Accepted { .. } => "AC",
+            Verdict::WrongAnswer { .. } => "WA",
+            Verdict::PresentationError { .. } => "PE",
+            Verdict::Points { .. } => "PTS",
+            Verdict::PartiallyCorrect { .. } => "PC",
+            Verdict::TimeLimitExceeded { .. } => "TLE",
+            Verdict::MemoryLimitExceeded { .. } => "MLE",
+            Verdict::OutputLimitExceeded { .. } => "OLE",
+            Verdict::SecurityViolation { .. } => "SV",
+            Verdict::RuntimeError { .. } => "RE",
+            Verdict::CheckerTimeLimitExceeded { .. } => "CHKTLE",
+            Verdict::CheckerError { .. } => "CHKRE",
+            Verdict::Cancelled => "CANCELLED",
+        }
+    }
+
+    /// Output of the solution, `None` if it is not run
+    pub fn output(&self) -> Option<&ProgramOutput> {
+        match self {
+            Verdict::Accepted { output, .. }
+            | Verdict::WrongAnswer { output, .. }
+            | Verdict::PresentationError { output, .. }
+            | Verdict::Points { output, .. }
+            | Verdict::PartiallyCorrect { output, .. }
+            | Verdict::TimeLimitExceeded { output }
+            | Verdict::MemoryLimitExceeded { output }
+            | Verdict::OutputLimitExceeded { output }
+            | Verdict::SecurityViolation { output }
+            | Verdict::RuntimeError { output, .. }
+            | Verdict::CheckerTimeLimitExceeded { output, .. }
+            | Verdict::CheckerError { output, .. } => Some(output),
+            Verdict::CompileError { .. }
+            | Verdict::CompileTimeLimitExceeded
+            | Verdict::Cancelled => None,
+        }
+    }
+
+    pub fn checker_message(&self) -> Option<&str> {
+        match self {
+            Verdict::Accepted {
+                checker_message, ..
+            }
+            | Verdict::WrongAnswer {
+                checker_message, ..
+            }
+            | Verdict::PresentationError {
+                checker_message, ..
+            }
+            | Verdict::Points {
+                checker_message, ..
+            }
+            | Verdict::PartiallyCorrect {
+                checker_message, ..
+            }
+            | Verdict::CheckerTimeLimitExceeded {
+                checker_message, ..
+            }
+            | Verdict::CheckerError {
+                checker_message, ..
+            } => Some(checker_message),
+            _ => None,
+        }
+    }
+
+    /// Map the outcome of a testlib checker to the verdict.
+    /// The result file is preferred since it carries the points,
+    /// otherwise the exit code decides:
+    ///
+    /// | exit code | outcome                                      |
+    /// |-----------|----------------------------------------------|
+    /// | 0         | accepted                                     |
+    /// | 1         | wrong answer                                 |
+    /// | 2, 4      | presentation error, wrong output format      |
+    /// | 3         | fail, the checker itself failed              |
+    /// | 7         | points, the score is printed after `points`  |
+    /// | 8         | unexpected eof, treated as wrong answer      |
+    /// | 50 + n    | partially correct with partial type n ≤ 100  |
+    ///
+    /// The last row only holds for a checker built with `TESTSYS` defined.
+    /// Without it, which is the default of the bundled testlib.h, a partially correct checker
+    /// exits with plain n, so it is told apart by the `partially correct (n)` it prints to stderr.
+    /// Any other exit code is a checker error, as are 126 and 127 of a checker which can not be run.
+    pub fn from_checker(
+        output: ProgramOutput,
+        checker: ProgramSimpleOutput,
+        result: Option<TestlibResult>,
+    ) -> Self {
+        let checker_message = checker.stderr;
+        match checker.termination {
+            ProgramTermination::Exited | ProgramTermination::SecurityViolation => {}
+            ProgramTermination::Cancelled => return Verdict::Cancelled,
+            ProgramTermination::TimeLimitExceeded
+            | ProgramTermination::MemoryLimitExceeded
+            | ProgramTermination::OutputLimitExceeded => {
+                return Verdict::CheckerTimeLimitExceeded {
+                    output,
+                    checker_message,
+                }
+            }
+        }
+        if let Some(result) = result {
+            return Self::from_testlib_result(output, result, checker_message);
+        }
+        if let Some(pctype) = parse_partially_correct_message(&checker_message) {
+            return Verdict::PartiallyCorrect {
+                output,
+                checker_message,
+                score: pctype as f64,
+            };
+        }
+        match checker.exit_code {
+            0 => Verdict::Accepted {
+                output,
+                checker_message,
+            },
+            1 | 8 => Verdict::WrongAnswer {
+                output,
+                checker_message,
+            },
+            2 | 4 => Verdict::PresentationError {
+                output,
+                checker_message,
+            },
+            POINTS_EXIT_CODE => match parse_points_message(&checker_message) {
+                Some(score) => Verdict::Points {
+                    output,
+                    checker_message,
+                    score,
+                },
+                None => Verdict::CheckerError {
+                    output,
+                    checker_message,
+                },
+            },
+            code @ PC_BASE_EXIT_CODE..=PC_MAX_EXIT_CODE
+                if !SHELL_FAILURE_EXIT_CODES.contains(&code) =>
+            {
+                Verdict::PartiallyCorrect {
+                    output,
+                    checker_message,
+                    score: (code - PC_BASE_EXIT_CODE) as f64,
+                }
+            }
+            _ => Verdict::CheckerError {
+                output,
+                checker_message,
+            },
+        }
+    }
+
+    fn from_testlib_result(
+        output: ProgramOutput,
+        result: TestlibResult,
+        checker_stderr: String,
+    ) -> Self {
+        let checker_message = if result.message.is_empty() {
+            checker_stderr
+        } else {
+            result.message
+        };
+        match (result.outcome.as_str(), result.points, result.pctype) {
+            ("accepted", _, _) => Verdict::Accepted {
+                output,
+                checker_message,
+            },
+            ("wrong-answer" | "unexpected-eof", _, _) => Verdict::WrongAnswer {
+                output,
+                checker_message,
+            },
+            ("presentation-error", _, _) => Verdict::PresentationError {
+                output,
+                checker_message,
+            },
+            ("points" | "relative-scoring", Some(score), _) => Verdict::Points {
+                output,
+                checker_message,
+                score,
+            },
+            ("partially-correct", _, Some(pctype)) => Verdict::PartiallyCorrect {
+                output,
+                checker_message,
+                score: pctype as f64,
+            },
+            _ => Verdict::CheckerError {
+                output,
+                checker_message,
+            },
+        }
+    }
+}
+
+/// testlib prints `partially correct (<pctype>) <message>` to stderr when it quits with a partial type
+fn parse_partially_correct_message(message: &str) -> Option<i32> {
+    let rest = message.trim_start().strip_prefix("partially correct (")?;
+    let (pctype, _) = rest.split_once(')')?;
+    pctype.parse().ok()
+}
+
+/// testlib prints `points <score> <message>` to stderr when it quits with points
+fn parse_points_message(message: &str) -> Option<f64> {
+    message
+        .trim_start()
+        .strip_prefix("points")?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::runner::process::ResourceUsage;
+
+    use super::*;
+
+    fn program_output() -> ProgramOutput {
+        ProgramOutput::Full {
+            exit_code: 0,
+            termination: ProgramTermination::Exited,
+            usage: ResourceUsage::default(),
+            time_limit_millis: 1000,
+            content: "3\n".to_string(),
+            output_file: PathBuf::from("case.out"),
+        }
+    }
+
+    fn checker_output(exit_code: i32, termination: ProgramTermination) -> ProgramSimpleOutput {
+        ProgramSimpleOutput {
+            exit_code,
+            stdout: String::new(),
+            stderr: "message".to_string(),
+            termination,
+            usage: ResourceUsage::default(),
+        }
+    }
+
+    #[test]
+    fn test_testlib_exit_code() {
+        let verdict = |exit_code| {
+            Verdict::from_checker(
+                program_output(),
+                checker_output(exit_code, ProgramTermination::Exited),
+                None,
+            )
+        };
+        assert!(matches!(verdict(0), Verdict::Accepted { .. }));
+        assert!(matches!(verdict(1), Verdict::WrongAnswer { .. }));
+        assert!(matches!(verdict(2), Verdict::PresentationError { .. }));
+        assert!(matches!(verdict(3), Verdict::CheckerError { .. }));
+        assert!(matches!(verdict(6), Verdict::CheckerError { .. }));
+        assert!(matches!(verdict(8), Verdict::WrongAnswer { .. }));
+        assert!(matches!(
+            verdict(53),
+            Verdict::PartiallyCorrect { score, .. } if score == 3.0
+        ));
+        assert!(matches!(
+            verdict(150),
+            Verdict::PartiallyCorrect { score, .. } if score == 100.0
+        ));
+        assert!(matches!(verdict(127), Verdict::CheckerError { .. }));
+        assert!(matches!(verdict(151), Verdict::CheckerError { .. }));
+        assert!(matches!(verdict(255), Verdict::CheckerError { .. }));
+        assert!(matches!(verdict(-1), Verdict::CheckerError { .. }));
+    }
+
+    #[test]
+    fn test_partially_correct_without_testsys() {
+        // `quitf(_pc(3), ...)` of a checker built against the bundled testlib.h without TESTSYS
+        let mut checker = checker_output(3, ProgramTermination::Exited);
+        checker.stderr = "partially correct (3) half of the answers".to_string();
+        let verdict = Verdict::from_checker(program_output(), checker, None);
+        assert!(matches!(verdict, Verdict::PartiallyCorrect { score, .. } if score == 3.0));
+
+        let mut checker = checker_output(0, ProgramTermination::Exited);
+        checker.stderr = "partially correct (0) ".to_string();
+        let verdict = Verdict::from_checker(program_output(), checker, None);
+        assert!(matches!(verdict, Verdict::PartiallyCorrect { score, .. } if score == 0.0));
+
+        // the result file is preferred over both the exit code and stderr
+        let result = TestlibResult::parse(
+            "<result outcome = \"partially-correct\" pctype = \"1\">half</result>",
+        )
+        .unwrap();
+        let verdict = Verdict::from_checker(
+            program_output(),
+            checker_output(1, ProgramTermination::Exited),
+            Some(result),
+        );
+        assert!(matches!(verdict, Verdict::PartiallyCorrect { score, .. } if score == 1.0));
+    }
+
+    #[test]
+    fn test_points_message() {
+        let mut checker = checker_output(POINTS_EXIT_CODE, ProgramTermination::Exited);
+        checker.stderr = "points 12.5 ok".to_string();
+        let verdict = Verdict::from_checker(program_output(), checker, None);
+        assert!(matches!(verdict, Verdict::Points { score, .. } if score == 12.5));
+    }
+
+    #[test]
+    fn test_testlib_result() {
+        let result = TestlibResult::parse(
+            "<?xml version=\"1.0\" encoding=\"windows-1251\"?><result outcome = \"points\" points = \"0.75\">3 of 4 &lt;ok&gt;</result>\n",
+        )
+        .unwrap();
+        assert_eq!(result.outcome, "points");
+        assert_eq!(result.points, Some(0.75));
+        assert_eq!(result.message, "3 of 4 <ok>");
+
+        let verdict = Verdict::from_checker(
+            program_output(),
+            checker_output(POINTS_EXIT_CODE, ProgramTermination::Exited),
+            Some(result),
+        );
+        assert!(matches!(
+            verdict,
+            Verdict::Points { score, ref checker_message, .. } if score == 0.75 && checker_message == "3 of 4 <ok>"
+        ));
+
+        let result = TestlibResult::parse(
+            "<result outcome = \"partially-correct\" pctype = \"20\">half</result>",
+        )
+        .unwrap();
+        assert_eq!(result.pctype, Some(20));
+    }
+
+    #[test]
+    fn test_result_matches_serialized_tag() {
+        let verdicts = [
+            Verdict::CompileTimeLimitExceeded,
+            Verdict::Points {
+                output: program_output(),
+                checker_message: String::new(),
+                score: 1.0,
+            },
+            Verdict::RuntimeError {
+                output: program_output(),
+                sanitizer: None,
+                backtrace: None,
+            },
+            Verdict::CheckerTimeLimitExceeded {
+                output: program_output(),
+                checker_message: String::new(),
+            },
+            Verdict::Cancelled,
+        ];
+        for verdict in verdicts {
+            assert_eq!(
+                serde_json::to_value(&verdict).unwrap()["result"],
+                verdict.result()
+            );
+        }
+    }
+
+    #[test]
+    fn test_checker_timeout() {
+        let verdict = Verdict::from_checker(
+            program_output(),
+            checker_output(0, ProgramTermination::TimeLimitExceeded),
+            None,
+        );
+        assert_eq!(
+            verdict,
+            Verdict::CheckerTimeLimitExceeded {
+                output: program_output(),
+                checker_message: "message".to_string()
+            }
+        );
+    }
+}
@@ -10,6 +10,7 @@ pub mod commands;
 pub mod config;
 pub mod database;
 pub mod document;
+pub mod judge;
 pub mod model;
 pub mod runner;
 pub mod schema;
@@ -26,6 +27,8 @@ pub fn run() {
             commands::database::WorkspaceConfigUpdateEvent,
             commands::runner::LanguageServerEvent,
             commands::runner::ProgramOutputEvent,
+            commands::judge::JudgeProgressEvent,
+            commands::judge::StressProgressEvent,
         ])
         .commands(collect_commands![
             commands::exit_app::<tauri::Wry>,
@@ -38,6 +41,7 @@ pub fn run() {
             commands::database::create_problem,
             commands::database::create_solution,
             commands::database::create_checker,
+            commands::database::get_checkers,
             commands::database::get_solution,
             commands::database::delete_problem,
             commands::database::delete_solution,
@@ -45,6 +49,7 @@ pub fn run() {
             commands::database::update_solution,
             commands::database::create_testcase,
             commands::database::delete_testcase,
+            commands::database::update_testcase,
             commands::database::get_testcases,
             commands::database::get_workspace_config,
             commands::database::set_workspace_config::<tauri::Wry>,
@@ -62,7 +67,18 @@ pub fn run() {
             commands::runner::execute_program_callback,
             commands::runner::write_file_to_task_tag,
             commands::runner::execute_program,
-            commands::runner::execute_program_detached
+            commands::runner::execute_program_detached,
+            commands::runner::cancel_task,
+            commands::judge::compile_solution,
+            commands::judge::judge_testcase,
+            commands::judge::judge_all_testcases,
+            commands::judge::get_runs,
+            commands::judge::get_run_output,
+            commands::judge::get_run_status,
+            commands::judge::validate_testcases,
+            commands::judge::stress_test,
+            commands::judge::minimize_testcase,
+            commands::judge::regenerate_testcase
         ]);
 
     #[cfg(debug_assertions)]
@@ -117,6 +133,7 @@ pub fn run() {
             setup::setup_competitive_companion_listener(app)?;
 
             app.manage(commands::runner::LangServerState::default());
+            app.manage(commands::runner::TaskRegistry::default());
 
             Ok(())
         })
@@ -126,6 +143,7 @@ pub fn run() {
             RunEvent::Exit => {
                 let state = handle.state::<commands::runner::LangServerState>();
                 log::trace!("Recycling external resources");
+                handle.state::<commands::runner::TaskRegistry>().cancel_all();
                 block_in_place(|| {
                     block_on(async {
                         // ignore the result
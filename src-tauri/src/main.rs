@@ -2,5 +2,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // started again inside a terminal to run a detached program
+    if let Some(code) = algorimejo_lib::runner::pauser::run_if_requested() {
+        std::process::exit(code);
+    }
     algorimejo_lib::run()
 }
@@ -1,9 +1,17 @@
 use chrono::NaiveDateTime;
 use diesel::prelude::*;
 use diesel::Selectable;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use specta::Type;
 
+/// Tells a missing field from `null` in a changeset: a missing field is `None` and leaves the column
+/// alone, `null` is `Some(None)` which diesel writes as NULL
+fn deserialize_clearable<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Option<String>>, D::Error> {
+    Option::<String>::deserialize(deserializer).map(Some)
+}
+
 //TODO: Implement the statement enum and add it to database
 // #[derive(Debug, Serialize, Deserialize, Type)]
 // #[serde(tag = "type")]
@@ -34,6 +42,16 @@ pub struct Problem {
     pub modified_datetime: NaiveDateTime,
     pub time_limit: i32,
     pub memory_limit: i32,
+    /// The solution talks with an interactor instead of reading a static input
+    pub interactive: bool,
+    /// Id of the interactor program in the checker table
+    pub interactor: Option<String>,
+    /// Name of the file the solution reads its input from, `None` means stdin
+    pub input_file: Option<String>,
+    /// Name of the file the solution writes its output to, `None` means stdout
+    pub output_file: Option<String>,
+    /// Id of the program in the checker table which validates the testcase inputs
+    pub validator: Option<String>,
     pub solutions: Vec<Solution>,
 }
 
@@ -48,6 +66,35 @@ pub struct ProblemChangeset {
     pub checker: Option<String>,
     pub time_limit: Option<i32>,
     pub memory_limit: Option<i32>,
+    pub interactive: Option<bool>,
+    /// `null` removes the interactor
+    #[serde(
+        default,
+        deserialize_with = "deserialize_clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub interactor: Option<Option<String>>,
+    /// `null` reads the input from stdin again
+    #[serde(
+        default,
+        deserialize_with = "deserialize_clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub input_file: Option<Option<String>>,
+    /// `null` writes the output to stdout again
+    #[serde(
+        default,
+        deserialize_with = "deserialize_clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_file: Option<Option<String>>,
+    /// `null` removes the validator
+    #[serde(
+        default,
+        deserialize_with = "deserialize_clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub validator: Option<Option<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Queryable, Identifiable, Type)]
@@ -87,6 +134,11 @@ pub struct ProblemRow {
     pub memory_limit: i32,
     pub create_datetime: NaiveDateTime,
     pub modified_datetime: NaiveDateTime,
+    pub interactive: bool,
+    pub interactor: Option<String>,
+    pub input_file: Option<String>,
+    pub output_file: Option<String>,
+    pub validator: Option<String>,
 }
 
 #[derive(Debug, Queryable, Selectable)]
@@ -134,4 +186,58 @@ pub struct TestCase {
     pub problem_id: String,
     pub input_document_id: String,
     pub answer_document_id: String,
+    /// Id of the solution printing the input, `None` if the input is written by hand
+    pub generator: Option<String>,
+    /// Appended to the run command of the generator
+    pub generator_args: Option<String>,
+    /// Id of the solution printing the answer of a generated input
+    pub reference: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Type, AsChangeset)]
+#[diesel(table_name = crate::schema::test_cases)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct TestCaseChangeset {
+    /// `null` turns the testcase back into a handwritten one
+    #[serde(
+        default,
+        deserialize_with = "deserialize_clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub generator: Option<Option<String>>,
+    /// `null` runs the generator without arguments
+    #[serde(
+        default,
+        deserialize_with = "deserialize_clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub generator_args: Option<Option<String>>,
+    /// `null` removes the reference solution
+    #[serde(
+        default,
+        deserialize_with = "deserialize_clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reference: Option<Option<String>>,
+}
+
+/// A verdict of a solution on a testcase, kept as the history of its runs
+#[derive(Debug, Serialize, Deserialize, Queryable, Identifiable, Selectable, Type, Insertable)]
+#[diesel(table_name = crate::schema::runs)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Run {
+    pub id: String,
+    pub solution_id: String,
+    pub testcase_id: String,
+    /// Hash of the source the solution is judged with, tells whether it is changed since
+    pub source_hash: String,
+    /// Result of the verdict, like `AC`
+    pub verdict: String,
+    /// CPU time, or the wall time if it is not available
+    pub time_millis: Option<i32>,
+    pub memory_kib: Option<i32>,
+    pub checker_message: Option<String>,
+    /// Name of the file holding the output of the solution in the runs folder of the workspace
+    pub output_file: Option<String>,
+    pub create_datetime: NaiveDateTime,
 }
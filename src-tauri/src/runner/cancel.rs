@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+/// A flag shared by everyone involved in a task, once it is cancelled
+/// every program launched with it is killed as soon as possible
+#[derive(Debug, Clone)]
+pub struct CancelToken {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl Default for CancelToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Self {
+            sender: Arc::new(sender),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.sender.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// Resolves once the token is cancelled
+    pub async fn cancelled(&self) {
+        let mut receiver = self.sender.subscribe();
+        // the sender is owned by self, so the channel can not be closed here
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+}
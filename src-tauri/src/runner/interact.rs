@@ -0,0 +1,523 @@
+//! Run a solution against an interactor, the stdout of each program is piped to the stdin of the other
+use std::{path::Path, process::Command, time::Duration};
+
+use anyhow::Result;
+use log::trace;
+use tokio::{
+    fs::File,
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader, BufWriter},
+    process::ChildStdin,
+    sync::mpsc,
+    task::JoinHandle,
+    time::Instant,
+};
+
+use crate::runner::{
+    cancel::CancelToken,
+    process::{ChildProcess, ProcessExit},
+    resource::{cap_memory, resident_memory_kib},
+    run::{
+        read_program_output, LineSplitter, ProgramOutput, ProgramSimpleOutput, ProgramTermination,
+        RunLimit, WATCHDOG_INTERVAL,
+    },
+};
+
+const CHUNK_SIZE: usize = 4096;
+/// How long the interactor may keep running after the time limit of the solution
+const INTERACTOR_GRACE_MILLIS: u128 = 3000;
+
+/// Which program wrote a line of the transcript
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    SolutionToInteractor,
+    InteractorToSolution,
+}
+
+impl Direction {
+    fn prefix(&self) -> &'static str {
+        match self {
+            Direction::SolutionToInteractor => "> ",
+            Direction::InteractorToSolution => "< ",
+        }
+    }
+
+    fn opposite(&self) -> Direction {
+        match self {
+            Direction::SolutionToInteractor => Direction::InteractorToSolution,
+            Direction::InteractorToSolution => Direction::SolutionToInteractor,
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Direction::SolutionToInteractor => 0,
+            Direction::InteractorToSolution => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InteractiveOutput {
+    /// The output file of the solution is the transcript of the interaction
+    pub solution: ProgramOutput,
+    /// The stdout of the interactor is consumed by the solution, so only stderr is collected
+    pub interactor: ProgramSimpleOutput,
+}
+
+/// Forward every chunk read from `from` to `to` at once, reporting it to the transcript.
+/// A prompt is not required to end with a newline, the lines are only split for the transcript.
+/// Once the receiver has exited the rest is still drained,
+/// so that the sender will not be blocked by a full pipe.
+async fn pump<R: AsyncRead + Unpin>(
+    from: R,
+    to: ChildStdin,
+    direction: Direction,
+    transcript: mpsc::UnboundedSender<(Direction, Vec<u8>)>,
+) -> Result<()> {
+    let mut from = from;
+    let mut to = Some(to);
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = from.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+        let chunk = &buffer[..n];
+        if let Some(writer) = to.as_mut() {
+            let written = async {
+                writer.write_all(chunk).await?;
+                writer.flush().await
+            }
+            .await;
+            if let Err(e) = written {
+                trace!("{:?} pipe is closed: {}", direction, e);
+                to = None;
+            }
+        }
+        let _ = transcript.send((direction, chunk.to_vec()));
+    }
+    // dropping the stdin sends EOF to the receiver
+    std::mem::drop(to);
+    Ok(())
+}
+
+/// The lines passed between the solution and the interactor, cut off at the output limit
+struct Transcript {
+    writer: BufWriter<File>,
+    lines: [LineSplitter; 2],
+    size: u64,
+    limit: u64,
+    is_full: bool,
+}
+
+impl Transcript {
+    fn new(file: File, limit: u64) -> Self {
+        Self {
+            writer: BufWriter::new(file),
+            lines: Default::default(),
+            size: 0,
+            limit,
+            is_full: false,
+        }
+    }
+
+    /// Save the complete lines of a chunk,
+    /// a prompt of the other side without newline is complete once this side answers
+    async fn push<C: FnMut(&str)>(
+        &mut self,
+        direction: Direction,
+        chunk: &[u8],
+        stdout_line_callback: &mut C,
+    ) -> Result<()> {
+        if self.is_full {
+            return Ok(());
+        }
+        let mut lines = Vec::new();
+        let other = direction.opposite();
+        self.lines[other.index()].finish(&mut |line| lines.push((other, line.to_string())));
+        self.lines[direction.index()]
+            .push(chunk, &mut |line| lines.push((direction, line.to_string())));
+        self.write(lines, stdout_line_callback).await
+    }
+
+    /// Save the last lines once both sides are closed
+    async fn finish<C: FnMut(&str)>(&mut self, stdout_line_callback: &mut C) -> Result<()> {
+        let mut lines = Vec::new();
+        for direction in [
+            Direction::SolutionToInteractor,
+            Direction::InteractorToSolution,
+        ] {
+            self.lines[direction.index()]
+                .finish(&mut |line| lines.push((direction, line.to_string())));
+        }
+        self.write(lines, stdout_line_callback).await
+    }
+
+    async fn write<C: FnMut(&str)>(
+        &mut self,
+        lines: Vec<(Direction, String)>,
+        stdout_line_callback: &mut C,
+    ) -> Result<()> {
+        for (direction, line) in lines {
+            let size = (direction.prefix().len() + line.len() + 1) as u64;
+            if self.is_full || self.size + size > self.limit {
+                trace!("transcript exceeds the output limit, the rest is dropped");
+                self.is_full = true;
+                break;
+            }
+            self.size += size;
+            if direction == Direction::SolutionToInteractor {
+                stdout_line_callback(&line);
+            }
+            self.writer.write_all(direction.prefix().as_bytes()).await?;
+            self.writer.write_all(line.as_bytes()).await?;
+            self.writer.write_all(b"\n").await?;
+        }
+        Ok(())
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.writer.flush().await?;
+        self.writer.shutdown().await?;
+        Ok(())
+    }
+}
+
+fn termination_by_limit(
+    pid: u32,
+    elapsed: Duration,
+    timeout_millis: u128,
+    memory_limit_kib: Option<u64>,
+) -> Option<ProgramTermination> {
+    if elapsed.as_millis() > timeout_millis {
+        return Some(ProgramTermination::TimeLimitExceeded);
+    }
+    if let Some(memory_limit) = memory_limit_kib {
+        if resident_memory_kib(pid).unwrap_or(0) > memory_limit {
+            return Some(ProgramTermination::MemoryLimitExceeded);
+        }
+    }
+    None
+}
+
+/// Launch the solution and the interactor with their stdin and stdout cross-wired.
+/// `limit` applies to the solution, the interactor is only killed if it is still running
+/// a while after the time limit of the solution.
+/// Every line passed between them is saved to `transcript_file`,
+/// prefixed with `> ` if it is written by the solution and `< ` if it is written by the interactor.
+/// The solution is killed once it writes more than the output limit,
+/// and the transcript is cut off at the output limit.
+pub async fn launch_interactive<C1: FnMut(&str), C2: FnMut(&str)>(
+    solution_cmd: Command,
+    interactor_cmd: Command,
+    transcript_file: &Path,
+    limit: RunLimit,
+    cancel: &CancelToken,
+    mut stdout_line_callback: C1,
+    mut stderr_line_callback: C2,
+) -> Result<InteractiveOutput> {
+    if let Some(p) = transcript_file.parent() {
+        if !p.exists() {
+            tokio::fs::create_dir_all(p).await?;
+        }
+    }
+    let mut transcript = Transcript::new(
+        File::create(transcript_file).await?,
+        limit.output_limit_bytes.unwrap_or(u64::MAX),
+    );
+
+    let mut solution_cmd = solution_cmd;
+    if let Some(memory_limit) = limit.memory_limit_kib {
+        cap_memory(&mut solution_cmd, memory_limit);
+    }
+    let (mut interactor, interactor_stdin, interactor_stdout, interactor_stderr) =
+        ChildProcess::spawn(interactor_cmd)?;
+    let (mut solution, solution_stdin, solution_stdout, solution_stderr) =
+        ChildProcess::spawn(solution_cmd)?;
+    let (solution_pid, interactor_pid) = (solution.id(), interactor.id());
+    trace!(
+        "interact: solution pid {}, interactor pid {}",
+        solution_pid,
+        interactor_pid
+    );
+
+    let (transcript_tx, mut transcript_rx) = mpsc::unbounded_channel();
+    let pumps: [JoinHandle<Result<()>>; 2] = [
+        tokio::spawn(pump(
+            solution_stdout,
+            interactor_stdin,
+            Direction::SolutionToInteractor,
+            transcript_tx.clone(),
+        )),
+        tokio::spawn(pump(
+            interactor_stdout,
+            solution_stdin,
+            Direction::InteractorToSolution,
+            transcript_tx,
+        )),
+    ];
+    let interactor_message: JoinHandle<Result<String>> = tokio::spawn(async move {
+        let mut message = Vec::new();
+        BufReader::new(interactor_stderr)
+            .read_to_end(&mut message)
+            .await?;
+        Ok(String::from_utf8_lossy(&message).into_owned())
+    });
+
+    let mut solution_stderr = solution_stderr;
+    let mut stderr_buffer = vec![0u8; 4096];
+    let mut stderr_lines = LineSplitter::default();
+    let mut solution_exit: Option<ProcessExit> = None;
+    let mut interactor_exit: Option<ProcessExit> = None;
+    let mut solution_termination = ProgramTermination::Exited;
+    let mut interactor_termination = ProgramTermination::Exited;
+    let mut is_transcript_closed = false;
+    let mut is_stderr_eof = false;
+    let output_limit = limit.output_limit_bytes.unwrap_or(u64::MAX);
+    let mut solution_output_size: u64 = 0;
+
+    let start_time = Instant::now();
+    let mut watchdog = tokio::time::interval(WATCHDOG_INTERVAL);
+    let cancelled = cancel.cancelled();
+    tokio::pin!(cancelled);
+    let mut is_cancelled = false;
+    loop {
+        if solution_exit.is_some()
+            && interactor_exit.is_some()
+            && is_transcript_closed
+            && is_stderr_eof
+        {
+            break;
+        }
+        tokio::select! {
+            res = transcript_rx.recv(), if !is_transcript_closed => {
+                let Some((direction, chunk)) = res else {
+                    is_transcript_closed = true;
+                    transcript.finish(&mut stdout_line_callback).await?;
+                    continue;
+                };
+                if direction == Direction::SolutionToInteractor {
+                    solution_output_size += chunk.len() as u64;
+                    if solution_output_size > output_limit
+                        && solution_exit.is_none()
+                        && solution_termination == ProgramTermination::Exited
+                    {
+                        trace!("solution {} output exceeds limit! killing process", solution_pid);
+                        solution_termination = ProgramTermination::OutputLimitExceeded;
+                        solution.kill().await?;
+                    }
+                }
+                transcript.push(direction, &chunk, &mut stdout_line_callback).await?;
+            },
+            res = solution_stderr.read(&mut stderr_buffer), if !is_stderr_eof => {
+                match res {
+                    Ok(n) if n > 0 => stderr_lines.push(&stderr_buffer[..n], &mut stderr_line_callback),
+                    _ => {
+                        stderr_lines.finish(&mut stderr_line_callback);
+                        is_stderr_eof = true;
+                    }
+                }
+            },
+            res = solution.wait(), if solution_exit.is_none() => {
+                trace!("solution {} exited", solution_pid);
+                solution_exit = Some(res?);
+            },
+            res = interactor.wait(), if interactor_exit.is_none() => {
+                trace!("interactor {} exited", interactor_pid);
+                interactor_exit = Some(res?);
+            },
+            _ = &mut cancelled, if !is_cancelled => {
+                trace!("interaction cancelled! killing solution and interactor");
+                is_cancelled = true;
+                if solution_exit.is_none() {
+                    solution_termination = ProgramTermination::Cancelled;
+                    solution.kill().await?;
+                }
+                if interactor_exit.is_none() {
+                    interactor_termination = ProgramTermination::Cancelled;
+                    interactor.kill().await?;
+                }
+            },
+            _ = watchdog.tick() => {
+                let elapsed = start_time.elapsed();
+                if solution_exit.is_none() && solution_termination == ProgramTermination::Exited {
+                    if let Some(termination) = termination_by_limit(
+                        solution_pid,
+                        elapsed,
+                        limit.timeout_millis,
+                        limit.memory_limit_kib,
+                    ) {
+                        trace!("solution {} {:?}! killing process", solution_pid, termination);
+                        solution_termination = termination;
+                        solution.kill().await?;
+                    }
+                }
+                if interactor_exit.is_none() && interactor_termination == ProgramTermination::Exited {
+                    if let Some(termination) =
+                        termination_by_limit(
+                        interactor_pid,
+                        elapsed,
+                        limit.timeout_millis + INTERACTOR_GRACE_MILLIS,
+                        None,
+                    )
+                    {
+                        trace!("interactor {} {:?}! killing process", interactor_pid, termination);
+                        interactor_termination = termination;
+                        interactor.kill().await?;
+                    }
+                }
+            }
+        }
+    }
+    transcript.close().await?;
+    for pump in pumps {
+        pump.await??;
+    }
+    let interactor_message = interactor_message.await??;
+
+    let solution_exit = solution_exit.unwrap();
+    let interactor_exit = interactor_exit.unwrap();
+    trace!(
+        "interaction finished, solution: {:?}, interactor: {:?}",
+        solution_exit,
+        interactor_exit
+    );
+    if solution_termination == ProgramTermination::Exited && solution_exit.is_security_violation() {
+        solution_termination = ProgramTermination::SecurityViolation;
+    }
+    if let (Some(memory_limit), Some(peak_memory)) =
+        (limit.memory_limit_kib, solution_exit.usage.peak_memory_kib)
+    {
+        if solution_termination == ProgramTermination::Exited && peak_memory as u64 > memory_limit {
+            solution_termination = ProgramTermination::MemoryLimitExceeded;
+        }
+    }
+
+    let solution = read_program_output(
+        transcript_file,
+        solution_exit.exit_code,
+        solution_termination,
+        solution_exit.usage,
+        limit.timeout_millis as u32,
+    )
+    .await?;
+    Ok(InteractiveOutput {
+        solution,
+        interactor: ProgramSimpleOutput {
+            exit_code: interactor_exit.exit_code,
+            stdout: String::new(),
+            stderr: interactor_message,
+            termination: interactor_termination,
+            usage: interactor_exit.usage,
+        },
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> Command {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(script);
+        cmd
+    }
+
+    async fn interact(
+        solution: &str,
+        interactor: &str,
+        output_limit: u64,
+    ) -> (InteractiveOutput, String) {
+        let transcript_file = std::env::temp_dir()
+            .join(format!("algorimejo-interact-{}", uuid::Uuid::new_v4()))
+            .join("transcript.out");
+        let output = launch_interactive(
+            sh(solution),
+            sh(interactor),
+            &transcript_file,
+            RunLimit {
+                timeout_millis: 5000,
+                memory_limit_kib: None,
+                output_limit_bytes: Some(output_limit),
+            },
+            &CancelToken::new(),
+            |_| {},
+            |_| {},
+        )
+        .await
+        .unwrap();
+        let transcript = std::fs::read_to_string(&transcript_file).unwrap();
+        let _ = std::fs::remove_dir_all(transcript_file.parent().unwrap());
+        (output, transcript)
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let (output, transcript) = interact(
+            "read a; echo $((a + 1))",
+            "echo 41; read b; echo got $b >&2",
+            1024,
+        )
+        .await;
+        assert_eq!(output.solution.termination(), ProgramTermination::Exited);
+        assert_eq!(output.interactor.stderr, "got 42\n");
+        assert_eq!(transcript, "< 41\n> 42\n");
+    }
+
+    #[tokio::test]
+    async fn test_prompt_without_newline() {
+        let (output, transcript) = interact(
+            "head -c 2 > /dev/null; echo ok",
+            "printf '? '; read b; test \"$b\" = ok",
+            1024,
+        )
+        .await;
+        assert_eq!(output.solution.termination(), ProgramTermination::Exited);
+        assert_eq!(output.interactor.termination, ProgramTermination::Exited);
+        assert_eq!(output.interactor.exit_code, 0);
+        assert_eq!(transcript, "< ? \n> ok\n");
+    }
+
+    #[tokio::test]
+    async fn test_output_limit() {
+        let (output, transcript) = interact("yes", "cat > /dev/null", 1000).await;
+        assert_eq!(
+            output.solution.termination(),
+            ProgramTermination::OutputLimitExceeded
+        );
+        assert!(transcript.len() <= 1000);
+    }
+
+    #[tokio::test]
+    async fn test_cancel() {
+        let transcript_file = std::env::temp_dir()
+            .join(format!("algorimejo-interact-{}", uuid::Uuid::new_v4()))
+            .join("transcript.out");
+        let cancel = CancelToken::new();
+        let token = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            token.cancel();
+        });
+        // both wait for the other one forever
+        let output = launch_interactive(
+            sh("read a"),
+            sh("read b"),
+            &transcript_file,
+            RunLimit {
+                timeout_millis: 8000,
+                memory_limit_kib: None,
+                output_limit_bytes: None,
+            },
+            &cancel,
+            |_| {},
+            |_| {},
+        )
+        .await
+        .unwrap();
+        let _ = std::fs::remove_dir_all(transcript_file.parent().unwrap());
+        assert_eq!(output.solution.termination(), ProgramTermination::Cancelled);
+        assert_eq!(output.interactor.termination, ProgramTermination::Cancelled);
+        assert!(output.solution.usage().wall_time_millis < 8000);
+    }
+}
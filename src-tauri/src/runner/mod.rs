@@ -3,9 +3,15 @@ use std::{path::PathBuf, process::Command};
 use log::trace;
 use once_cell::sync::Lazy;
 
+pub mod cancel;
 pub mod cmd;
+pub mod interact;
 pub mod lang_server;
+pub mod pauser;
+pub mod process;
+pub mod resource;
 pub mod run;
+pub mod sandbox;
 
 pub static BUNDLED_CHECKER_NAME: Lazy<Vec<&str>> = Lazy::new(|| {
     let chks = include_str!("bundle-chk.txt").lines().collect::<Vec<_>>();
@@ -0,0 +1,58 @@
+//! Keep the terminal window of a detached program open after it exits.
+//! The app executable itself is the wrapper outside Windows: it is started again inside the terminal
+//! with [`PAUSER_ARG`] in front of the program, reports how the program ended and waits for Enter.
+//! On Windows the bundled `consolepauser` does the same.
+use std::{
+    ffi::OsString,
+    io::{BufRead, Write},
+    process::{Command, ExitStatus},
+    time::Instant,
+};
+
+pub const PAUSER_ARG: &str = "--algorimejo-pause";
+
+fn describe_exit_status(status: &ExitStatus) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return format!("signal {}", signal);
+        }
+    }
+    format!("exit code {}", status.code().unwrap_or(-1))
+}
+
+/// Run the program given after [`PAUSER_ARG`] and wait for Enter,
+/// returns `None` if this process is not started as the wrapper
+pub fn run_if_requested() -> Option<i32> {
+    let mut args = std::env::args_os().skip(1);
+    if args.next()? != PAUSER_ARG {
+        return None;
+    }
+    let program = args.next().unwrap_or_default();
+    let args = args.collect::<Vec<OsString>>();
+
+    let start = Instant::now();
+    let status = Command::new(&program).args(args).status();
+    let elapsed = start.elapsed();
+    println!();
+    println!("--------------------------------");
+    let code = match status {
+        Ok(status) => {
+            println!(
+                "Process exited with {} after {:.3} s",
+                describe_exit_status(&status),
+                elapsed.as_secs_f64()
+            );
+            status.code().unwrap_or(1)
+        }
+        Err(e) => {
+            println!("Failed to launch {:?}: {}", program, e);
+            1
+        }
+    };
+    print!("Press Enter to close this window...");
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stdin().lock().read_line(&mut String::new());
+    Some(code)
+}
@@ -0,0 +1,364 @@
+/// Child process management for the runner
+/// On unix the runner reaps the child itself with `wait4`, so that the CPU time and peak memory
+/// reported by the kernel can be collected along with the exit status.
+/// On windows the child is put into a job object, which plays the role of the process group.
+use std::process::{Command, Stdio};
+
+use anyhow::Result;
+use log::trace;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tokio::{
+    process::{ChildStderr, ChildStdin, ChildStdout},
+    time::Instant,
+};
+
+use crate::runner::command_flag_hide_new_console;
+
+/// Resources consumed by a finished program
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct ResourceUsage {
+    pub wall_time_millis: u32,
+    /// User and system CPU time, not available on every platform
+    pub cpu_time_millis: Option<u32>,
+    /// Peak resident memory, not available on every platform
+    pub peak_memory_kib: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ProcessExit {
+    /// Exit code of the process, -1 if it was terminated by a signal
+    pub exit_code: i32,
+    /// The signal that terminated the process, always `None` on windows
+    pub signal: Option<i32>,
+    pub usage: ResourceUsage,
+}
+
+impl ProcessExit {
+    /// Killed by the seccomp filter of the sandbox
+    pub fn is_security_violation(&self) -> bool {
+        #[cfg(unix)]
+        return self.signal == Some(libc::SIGSYS);
+        #[cfg(not(unix))]
+        return false;
+    }
+}
+
+pub struct ChildProcess {
+    pid: u32,
+    start_time: Instant,
+    #[cfg(unix)]
+    waiter: Option<tokio::task::JoinHandle<std::io::Result<(i32, libc::rusage)>>>,
+    #[cfg(unix)]
+    exit: Option<ProcessExit>,
+    #[cfg(not(unix))]
+    child: tokio::process::Child,
+    #[cfg(windows)]
+    job: JobObject,
+}
+
+impl ChildProcess {
+    /// The process has not been reaped yet, so its pid is still valid to be signaled
+    #[cfg(unix)]
+    fn is_running(&self) -> bool {
+        self.waiter.as_ref().is_some_and(|w| !w.is_finished())
+    }
+
+    /// Spawn the command with piped stdin, stdout and stderr
+    pub fn spawn(mut cmd: Command) -> Result<(Self, ChildStdin, ChildStdout, ChildStderr)> {
+        command_flag_hide_new_console(&mut cmd);
+        cmd.stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stdin(Stdio::piped());
+        trace!("spawn program: {:?}", &cmd);
+        Self::spawn_piped(cmd)
+    }
+
+    #[cfg(unix)]
+    fn spawn_piped(mut cmd: Command) -> Result<(Self, ChildStdin, ChildStdout, ChildStderr)> {
+        use std::os::unix::process::CommandExt;
+        // the child leads a new process group, so that everything it spawns can be killed together
+        cmd.process_group(0);
+        let start_time = Instant::now();
+        let mut child = cmd.spawn()?;
+        let pid = child.id();
+        let stdin = ChildStdin::from_std(child.stdin.take().unwrap())?;
+        let stdout = ChildStdout::from_std(child.stdout.take().unwrap())?;
+        let stderr = ChildStderr::from_std(child.stderr.take().unwrap())?;
+        // std::process::Child neither waits nor kills on drop, the pid is owned by the waiter from now on
+        std::mem::drop(child);
+        let waiter = tokio::task::spawn_blocking(move || wait4(pid));
+        Ok((
+            Self {
+                pid,
+                start_time,
+                waiter: Some(waiter),
+                exit: None,
+            },
+            stdin,
+            stdout,
+            stderr,
+        ))
+    }
+
+    #[cfg(not(unix))]
+    fn spawn_piped(cmd: Command) -> Result<(Self, ChildStdin, ChildStdout, ChildStderr)> {
+        let mut cmd = tokio::process::Command::from(cmd);
+        cmd.kill_on_drop(true);
+        #[cfg(windows)]
+        let job = JobObject::new()?;
+        let start_time = Instant::now();
+        let mut child = cmd.spawn()?;
+        // whatever the child spawns from now on is in the job as well
+        #[cfg(windows)]
+        if let Some(handle) = child.raw_handle() {
+            job.assign(handle)?;
+        }
+        let pid = child.id().unwrap_or(0);
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+        Ok((
+            Self {
+                pid,
+                start_time,
+                child,
+                #[cfg(windows)]
+                job,
+            },
+            stdin,
+            stdout,
+            stderr,
+        ))
+    }
+
+    pub fn id(&self) -> u32 {
+        self.pid
+    }
+
+    /// Wait for the process to exit. This method is cancel safe.
+    #[cfg(unix)]
+    pub async fn wait(&mut self) -> Result<ProcessExit> {
+        if let Some(exit) = self.exit {
+            return Ok(exit);
+        }
+        let waiter = self.waiter.as_mut().expect("process waiter is missing");
+        let (status, rusage) = waiter.await??;
+        self.waiter = None;
+        let wall_time = self.start_time.elapsed();
+
+        let exit_code = if libc::WIFEXITED(status) {
+            libc::WEXITSTATUS(status)
+        } else {
+            -1
+        };
+        let signal = libc::WIFSIGNALED(status).then(|| libc::WTERMSIG(status));
+        let cpu_time_millis =
+            timeval_to_millis(rusage.ru_utime) + timeval_to_millis(rusage.ru_stime);
+        // ru_maxrss is in bytes on macOS and in kilobytes elsewhere
+        #[cfg(target_os = "macos")]
+        let peak_memory_kib = rusage.ru_maxrss as u64 / 1024;
+        #[cfg(not(target_os = "macos"))]
+        let peak_memory_kib = rusage.ru_maxrss as u64;
+
+        let exit = ProcessExit {
+            exit_code,
+            signal,
+            usage: ResourceUsage {
+                wall_time_millis: wall_time.as_millis() as u32,
+                cpu_time_millis: Some(cpu_time_millis as u32),
+                peak_memory_kib: Some(peak_memory_kib as u32),
+            },
+        };
+        self.exit = Some(exit);
+        Ok(exit)
+    }
+
+    #[cfg(not(unix))]
+    pub async fn wait(&mut self) -> Result<ProcessExit> {
+        let status = self.child.wait().await?;
+        Ok(ProcessExit {
+            exit_code: status.code().unwrap_or(-1),
+            signal: None,
+            usage: ResourceUsage {
+                wall_time_millis: self.start_time.elapsed().as_millis() as u32,
+                cpu_time_millis: None,
+                peak_memory_kib: None,
+            },
+        })
+    }
+
+    /// Kill the process together with its process group on unix
+    #[cfg(unix)]
+    pub async fn kill(&mut self) -> Result<()> {
+        // the pid is not recycled until the waiter reaps it, so it is safe to signal before that
+        if self.is_running() {
+            let ret = unsafe { libc::kill(-(self.pid as libc::pid_t), libc::SIGKILL) };
+            if ret == -1 {
+                let err = std::io::Error::last_os_error();
+                if err.raw_os_error() != Some(libc::ESRCH) {
+                    return Err(err.into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Kill the process together with everything in its job on windows
+    #[cfg(not(unix))]
+    pub async fn kill(&mut self) -> Result<()> {
+        #[cfg(windows)]
+        match self.job.terminate() {
+            Ok(()) => return Ok(()),
+            Err(e) => trace!("failed to terminate the job of process {}: {}", self.pid, e),
+        }
+        self.child.kill().await?;
+        Ok(())
+    }
+}
+
+/// A job killing every process in it once it is closed,
+/// so nothing the program spawned outlives the [`ChildProcess`]
+#[cfg(windows)]
+struct JobObject(windows_sys::Win32::Foundation::HANDLE);
+
+// the handle is only passed to thread safe system calls
+#[cfg(windows)]
+unsafe impl Send for JobObject {}
+#[cfg(windows)]
+unsafe impl Sync for JobObject {}
+
+#[cfg(windows)]
+impl JobObject {
+    fn new() -> std::io::Result<Self> {
+        use windows_sys::Win32::System::JobObjects::{
+            CreateJobObjectW, JobObjectExtendedLimitInformation, SetInformationJobObject,
+            JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+        };
+
+        let handle = unsafe { CreateJobObjectW(std::ptr::null(), std::ptr::null()) };
+        if handle.is_null() {
+            return Err(std::io::Error::last_os_error());
+        }
+        let job = Self(handle);
+        let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+        info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        let ret = unsafe {
+            SetInformationJobObject(
+                job.0,
+                JobObjectExtendedLimitInformation,
+                &info as *const JOBOBJECT_EXTENDED_LIMIT_INFORMATION as *const std::ffi::c_void,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            )
+        };
+        if ret == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(job)
+    }
+
+    fn assign(&self, process: std::os::windows::io::RawHandle) -> std::io::Result<()> {
+        let ret = unsafe {
+            windows_sys::Win32::System::JobObjects::AssignProcessToJobObject(self.0, process as _)
+        };
+        if ret == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn terminate(&self) -> std::io::Result<()> {
+        let ret = unsafe { windows_sys::Win32::System::JobObjects::TerminateJobObject(self.0, 1) };
+        if ret == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+impl Drop for JobObject {
+    fn drop(&mut self) {
+        unsafe {
+            windows_sys::Win32::Foundation::CloseHandle(self.0);
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for ChildProcess {
+    fn drop(&mut self) {
+        // same as `kill_on_drop`, the waiter thread will reap the process afterwards
+        if self.is_running() {
+            unsafe {
+                libc::kill(-(self.pid as libc::pid_t), libc::SIGKILL);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn wait4(pid: u32) -> std::io::Result<(i32, libc::rusage)> {
+    let mut status = 0;
+    let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        let ret = unsafe { libc::wait4(pid as libc::pid_t, &mut status, 0, &mut rusage) };
+        if ret != -1 {
+            return Ok((status, rusage));
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn timeval_to_millis(tv: libc::timeval) -> u64 {
+    tv.tv_sec as u64 * 1000 + tv.tv_usec as u64 / 1000
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    fn sh(script: &str) -> Command {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(script);
+        cmd
+    }
+
+    #[tokio::test]
+    async fn test_wait_exit_code() {
+        let (mut child, _, _, _) = ChildProcess::spawn(sh("exit 3")).unwrap();
+        let exit = child.wait().await.unwrap();
+        assert_eq!(exit.exit_code, 3);
+        assert_eq!(exit.signal, None);
+        assert!(exit.usage.cpu_time_millis.is_some());
+        // the exit is kept once the process is reaped
+        assert_eq!(child.wait().await.unwrap().exit_code, 3);
+        // killing a reaped process does nothing
+        child.kill().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_kill_process_group() {
+        // the background sleep holds the stdout open as long as it is alive
+        let (mut child, _stdin, mut stdout, _) =
+            ChildProcess::spawn(sh("sleep 10 & wait")).unwrap();
+        child.kill().await.unwrap();
+        let exit = child.wait().await.unwrap();
+        assert_eq!(exit.exit_code, -1);
+        assert_eq!(exit.signal, Some(libc::SIGKILL));
+        let mut rest = Vec::new();
+        tokio::time::timeout(Duration::from_secs(5), stdout.read_to_end(&mut rest))
+            .await
+            .expect("a descendant of the killed process is still alive")
+            .unwrap();
+        assert!(exit.usage.wall_time_millis < 5000);
+    }
+}
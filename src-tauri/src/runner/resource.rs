@@ -0,0 +1,76 @@
+use std::process::Command;
+
+/// Cap the data segment (heap and anonymous mappings) of the program at twice `limit_kib`,
+/// so that a runaway program fails to allocate instead of exhausting the memory of the machine.
+/// The cap counts the memory reserved rather than used, hence the headroom,
+/// the limit itself is still reported by the watchdog from the resident memory.
+#[cfg(unix)]
+pub fn cap_memory(cmd: &mut Command, limit_kib: u64) {
+    use std::os::unix::process::CommandExt;
+
+    let cap = limit_kib.saturating_mul(2 * 1024) as libc::rlim_t;
+    let setup = move || -> std::io::Result<()> {
+        let rlimit = libc::rlimit {
+            rlim_cur: cap,
+            rlim_max: cap,
+        };
+        if unsafe { libc::setrlimit(libc::RLIMIT_DATA, &rlimit) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    };
+    unsafe {
+        cmd.pre_exec(setup);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn cap_memory(_cmd: &mut Command, _limit_kib: u64) {}
+
+/// Resident memory (VmRSS) of a running process in KiB.
+///
+/// Only available on Linux, where it is read from `/proc/<pid>/status`.
+/// Returns `None` if the process has exited or the platform is not supported.
+#[cfg(target_os = "linux")]
+pub fn resident_memory_kib(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    parse_status_field_kib(&status, "VmRSS")
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn resident_memory_kib(_pid: u32) -> Option<u64> {
+    None
+}
+
+/// Parse a line like `VmRSS:    1234 kB` from the content of `/proc/<pid>/status`
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_status_field_kib(status: &str, field: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(field)?.strip_prefix(':'))
+        .and_then(|value| value.split_whitespace().next())
+        .and_then(|value| value.parse::<u64>().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_status_field() {
+        let status = "Name:\tmain\nVmPeak:\t   10240 kB\nVmRSS:\t    2048 kB\nThreads:\t1\n";
+        assert_eq!(parse_status_field_kib(status, "VmRSS"), Some(2048));
+        assert_eq!(parse_status_field_kib(status, "VmPeak"), Some(10240));
+        assert_eq!(parse_status_field_kib(status, "VmHWM"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cap_memory() {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("ulimit -d");
+        cap_memory(&mut cmd, 1024);
+        let output = cmd.output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "2048");
+    }
+}
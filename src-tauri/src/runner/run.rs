@@ -1,6 +1,6 @@
 use std::{
     path::{Path, PathBuf},
-    process::{Command, Stdio},
+    process::Command,
     time::Duration,
 };
 
@@ -10,154 +10,305 @@ use serde::{Deserialize, Serialize};
 use specta::Type;
 use tokio::{
     fs::File,
-    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, BufWriter},
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader, BufWriter},
+    process::ChildStdin,
+    task::JoinHandle,
     time::Instant,
 };
 
-use crate::runner::command_flag_hide_new_console;
+use crate::runner::{
+    cancel::CancelToken,
+    process::{ChildProcess, ResourceUsage},
+    resource::{cap_memory, resident_memory_kib},
+};
+
+/// How often the watchdog checks the elapsed time and memory usage of a running program
+pub const WATCHDOG_INTERVAL: Duration = Duration::from_millis(50);
+/// Buffer size of the pipes between the runner and a program, large tests are tens of MiB
+const PIPE_BUFFER_SIZE: usize = 64 * 1024;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
 pub struct ProgramSimpleOutput {
-    exit_code: i32,
-    stdout: String,
-    stderr: String,
-    is_timeout: bool,
+    pub exit_code: i32,
+    pub stdout: String,
+    pub stderr: String,
+    pub termination: ProgramTermination,
+    pub usage: ResourceUsage,
 }
 
 pub async fn launch_program_without_input(
-    mut cmd: Command,
+    cmd: Command,
     timeout_millis: u128,
+    cancel: &CancelToken,
 ) -> Result<ProgramSimpleOutput> {
-    command_flag_hide_new_console(&mut cmd);
-    let mut cmd = tokio::process::Command::from(cmd);
-    cmd.kill_on_drop(true)
-        .stderr(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stdin(Stdio::piped());
-    trace!("spawn program: {:?}", &cmd);
-    let mut child = cmd.spawn()?;
-    let pid = child.id().unwrap_or(0);
+    let (mut child, stdin, stdout, stderr) = ChildProcess::spawn(cmd)?;
+    // nothing is fed to the program, close stdin so it will not wait for input forever
+    std::mem::drop(stdin);
+    let pid = child.id();
     trace!("pid: {}", pid);
-    let mut stdout_reader = BufReader::new(child.stdout.take().unwrap());
-    let mut stderr_reader = BufReader::new(child.stderr.take().unwrap());
+    // output is drained while the program is running, otherwise it blocks once a pipe is full
+    let stdout_reader = spawn_read_to_string(stdout);
+    let stderr_reader = spawn_read_to_string(stderr);
 
-    let mut is_timeout = false;
+    let mut termination = ProgramTermination::Exited;
     let start_time = Instant::now();
+    let mut watchdog = tokio::time::interval(WATCHDOG_INTERVAL);
+    let cancelled = cancel.cancelled();
+    tokio::pin!(cancelled);
     loop {
         tokio::select! {
-            _ = tokio::time::sleep(Duration::from_millis(200)) => {
+            _ = watchdog.tick() => {
                 if start_time.elapsed().as_millis() > timeout_millis {
-                    is_timeout = true;
+                    termination = ProgramTermination::TimeLimitExceeded;
                     trace!("timeout! kill process {}", pid);
                     child.kill().await?;
                     break;
                 }
             }
+            _ = &mut cancelled => {
+                termination = ProgramTermination::Cancelled;
+                trace!("cancelled! kill process {}", pid);
+                child.kill().await?;
+                break;
+            }
             Ok(_) = child.wait() => {
                 trace!("program exited");
                 break;
             }
         }
     }
-    let exit_code = child.wait().await?.code().unwrap_or(-1);
-    trace!("process {} exit code: {}", pid, exit_code);
-
-    let mut stdout = String::new();
-    let mut stderr = String::new();
-    let (stdout_result, stderr_result) = tokio::join!(
-        stdout_reader.read_to_string(&mut stdout),
-        stderr_reader.read_to_string(&mut stderr)
+    let exit = child.wait().await?;
+    let exit_code = exit.exit_code;
+    trace!(
+        "process {} exit code: {}, usage: {:?}",
+        pid,
+        exit_code,
+        exit.usage
     );
-    let stdout_sz = stdout_result?;
-    let stderr_sz = stderr_result?;
+
+    let stdout = stdout_reader.await??;
+    let stderr = stderr_reader.await??;
     trace!(
         "collected program {} output: stdout: {} bytes, stderr: {} bytes",
         pid,
-        stdout_sz,
-        stderr_sz
+        stdout.len(),
+        stderr.len()
     );
 
     Ok(ProgramSimpleOutput {
         exit_code,
         stdout,
         stderr,
-        is_timeout,
+        termination,
+        usage: exit.usage,
+    })
+}
+
+/// Read everything from the reader, invalid UTF-8 is replaced instead of failing the whole read
+fn spawn_read_to_string<R: AsyncRead + Unpin + Send + 'static>(
+    reader: R,
+) -> JoinHandle<Result<String>> {
+    tokio::spawn(async move {
+        let mut content = Vec::new();
+        BufReader::with_capacity(PIPE_BUFFER_SIZE, reader)
+            .read_to_end(&mut content)
+            .await?;
+        Ok(String::from_utf8_lossy(&content).into_owned())
+    })
+}
+
+/// Split raw output into lines for display.
+/// A line may arrive across several chunks, and the last line may not end with a newline.
+#[derive(Debug, Default)]
+pub struct LineSplitter {
+    pending: Vec<u8>,
+}
+
+impl LineSplitter {
+    pub fn push<C: FnMut(&str)>(&mut self, chunk: &[u8], callback: &mut C) {
+        let mut rest = chunk;
+        while let Some(pos) = rest.iter().position(|b| *b == b'\n') {
+            if self.pending.is_empty() {
+                Self::emit(&rest[..pos], callback);
+            } else {
+                self.pending.extend_from_slice(&rest[..pos]);
+                Self::emit(&self.pending, callback);
+                self.pending.clear();
+            }
+            rest = &rest[pos + 1..];
+        }
+        self.pending.extend_from_slice(rest);
+    }
+
+    /// Report the last line if it does not end with a newline
+    pub fn finish<C: FnMut(&str)>(&mut self, callback: &mut C) {
+        if !self.pending.is_empty() {
+            Self::emit(&self.pending, callback);
+            self.pending.clear();
+        }
+    }
+
+    fn emit<C: FnMut(&str)>(line: &[u8], callback: &mut C) {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        callback(&String::from_utf8_lossy(line));
+    }
+}
+
+/// Copy the input file to the stdin of a program, then close it to signal the end of input.
+/// A program is allowed to exit without reading all of its input.
+fn spawn_feed_input(input: File, stdin: ChildStdin) -> JoinHandle<Result<u64>> {
+    tokio::spawn(async move {
+        let mut input = BufReader::with_capacity(PIPE_BUFFER_SIZE, input);
+        let mut stdin = stdin;
+        let result = tokio::io::copy_buf(&mut input, &mut stdin).await;
+        std::mem::drop(stdin);
+        match result {
+            Ok(size) => Ok(size),
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {
+                trace!("program closed its stdin before reading all input");
+                Ok(0)
+            }
+            Err(e) => Err(e.into()),
+        }
     })
 }
 
+/// Limits applied to a program launched by [`launch_program`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunLimit {
+    pub timeout_millis: u128,
+    /// Peak resident memory in KiB, `None` means unlimited
+    pub memory_limit_kib: Option<u64>,
+    /// Bytes written to stdout, `None` means unlimited
+    pub output_limit_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub enum ProgramTermination {
+    Exited,
+    TimeLimitExceeded,
+    MemoryLimitExceeded,
+    /// Killed as soon as it writes more than the output limit
+    OutputLimitExceeded,
+    /// Killed by the sandbox for making a forbidden system call
+    SecurityViolation,
+    /// Killed by a [`CancelToken`] before it exited
+    Cancelled,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
 #[serde(tag = "type")]
 pub enum ProgramOutput {
     Full {
         exit_code: i32,
-        is_timeout: bool,
+        termination: ProgramTermination,
+        usage: ResourceUsage,
+        /// The time limit the program is run with
+        time_limit_millis: u32,
         content: String,
         output_file: PathBuf,
     },
     Strip {
         exit_code: i32,
         size: u32,
-        is_timeout: bool,
+        termination: ProgramTermination,
+        usage: ResourceUsage,
+        /// The time limit the program is run with
+        time_limit_millis: u32,
         content: String,
         output_file: PathBuf,
     },
 }
 
+impl ProgramOutput {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ProgramOutput::Full { exit_code, .. } | ProgramOutput::Strip { exit_code, .. } => {
+                *exit_code
+            }
+        }
+    }
+
+    pub fn termination(&self) -> ProgramTermination {
+        match self {
+            ProgramOutput::Full { termination, .. } | ProgramOutput::Strip { termination, .. } => {
+                *termination
+            }
+        }
+    }
+
+    pub fn usage(&self) -> ResourceUsage {
+        match self {
+            ProgramOutput::Full { usage, .. } | ProgramOutput::Strip { usage, .. } => *usage,
+        }
+    }
+
+    pub fn time_limit_millis(&self) -> u32 {
+        match self {
+            ProgramOutput::Full {
+                time_limit_millis, ..
+            }
+            | ProgramOutput::Strip {
+                time_limit_millis, ..
+            } => *time_limit_millis,
+        }
+    }
+
+    pub fn output_file(&self) -> &Path {
+        match self {
+            ProgramOutput::Full { output_file, .. } | ProgramOutput::Strip { output_file, .. } => {
+                output_file
+            }
+        }
+    }
+}
+
 pub async fn launch_program<
     P: AsRef<Path>,
     S: AsRef<Path>,
     C1: FnMut(&str) -> (),
     C2: FnMut(&str) -> (),
 >(
-    mut cmd: Command,
+    cmd: Command,
     input: P,
     output_file: S,
-    timeout_millis: u128,
+    limit: RunLimit,
+    cancel: &CancelToken,
     mut stdout_line_callback: C1,
     mut stderr_line_callback: C2,
 ) -> Result<ProgramOutput> {
-    command_flag_hide_new_console(&mut cmd);
-    let mut cmd = tokio::process::Command::from(cmd);
-    cmd.kill_on_drop(true)
-        .stderr(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stdin(Stdio::piped());
-    trace!("spawn program: {:?}", &cmd);
-    let mut child = cmd.spawn()?;
-    let pid = child.id().unwrap_or(0);
-    trace!("pid: {}", pid);
-    let stdout = child.stdout.take().unwrap();
-    let stderr = child.stderr.take().unwrap();
-
-    {
-        let mut stdin = child.stdin.take().unwrap();
-
-        let mut input_reader = File::open(input.as_ref()).await?;
-        let mut buffer = [0u8; 8];
-        let mut input_sz = 0;
-        while let Ok(n) = input_reader.read(&mut buffer).await {
-            if n == 0 {
-                break;
-            }
-            input_sz += n as u64;
-            stdin.write_all(&buffer[..n]).await?;
-        }
-        stdin.flush().await?;
-        stdin.shutdown().await?;
-        trace!("redirect input {} bytes to process {}", input_sz, pid);
-    }
-
-    let mut stdout_stream = BufReader::new(stdout).lines();
-    let mut stderr_stream = BufReader::new(stderr).lines();
+    let input_reader = File::open(input.as_ref()).await?;
     let parent = output_file.as_ref().parent();
     if let Some(p) = parent {
         if !p.exists() {
             tokio::fs::create_dir_all(p).await?;
         }
     }
-    let mut output_file_writer = BufWriter::new(File::create(output_file.as_ref()).await?);
+    let mut output_file_writer =
+        BufWriter::with_capacity(PIPE_BUFFER_SIZE, File::create(output_file.as_ref()).await?);
+
+    let mut cmd = cmd;
+    if let Some(memory_limit) = limit.memory_limit_kib {
+        cap_memory(&mut cmd, memory_limit);
+    }
+    let (mut child, stdin, stdout, stderr) = ChildProcess::spawn(cmd)?;
+    let pid = child.id();
+    trace!("pid: {}", pid);
+    // input is fed while the output is drained, a program may print before it reads everything
+    let input_feeder = spawn_feed_input(input_reader, stdin);
+
+    let mut stdout = stdout;
+    let mut stderr = stderr;
+    let mut stdout_buffer = vec![0u8; PIPE_BUFFER_SIZE];
+    let mut stderr_buffer = vec![0u8; PIPE_BUFFER_SIZE];
+    let mut stdout_lines = LineSplitter::default();
+    let mut stderr_lines = LineSplitter::default();
     let start_time = Instant::now();
-    let mut is_timeout = false;
+    let mut watchdog = tokio::time::interval(WATCHDOG_INTERVAL);
+    let cancelled = cancel.cancelled();
+    tokio::pin!(cancelled);
+    let mut termination = ProgramTermination::Exited;
+    let mut output_size: u64 = 0;
     let mut is_stdout_eof = false;
     let mut is_stderr_eof = false;
     loop {
@@ -166,64 +317,244 @@ pub async fn launch_program<
             break;
         }
         tokio::select! {
-            Ok(res) = stdout_stream.next_line(),if !is_stdout_eof => {
-                if let Some(line) = res {
-                    trace!("program {} stdout -> {}", pid, line);
-                    stdout_line_callback(&line);
-                    output_file_writer.write_all(line.as_bytes()).await?;
-                    output_file_writer.write_all(b"\n").await?;
-                }else{
+            res = stdout.read(&mut stdout_buffer), if !is_stdout_eof => {
+                let n = res?;
+                if n == 0 {
                     trace!("program {} stdout is EOF", pid);
+                    stdout_lines.finish(&mut stdout_line_callback);
                     is_stdout_eof = true;
+                    continue;
+                }
+                let mut chunk = &stdout_buffer[..n];
+                output_size += n as u64;
+                let exceeded = limit
+                    .output_limit_bytes
+                    .filter(|output_limit| output_size > *output_limit);
+                if let Some(output_limit) = exceeded {
+                    // keep the output up to the limit for inspection
+                    chunk = &chunk[..n - (output_size - output_limit) as usize];
+                }
+                // the output file is a byte-exact copy, only the lines reported to the UI are decoded
+                output_file_writer.write_all(chunk).await?;
+                stdout_lines.push(chunk, &mut stdout_line_callback);
+                if exceeded.is_some() {
+                    termination = ProgramTermination::OutputLimitExceeded;
+                    trace!("program {} output exceeds limit! killing process", pid);
+                    child.kill().await?;
+                    break;
                 }
             },
-            Ok(res) = stderr_stream.next_line(),if !is_stderr_eof => {
-                if let Some(line) = res {
-                    trace!("program {} stderr -> {}", pid, line);
-                    stderr_line_callback(&line);
-                }else{
+            res = stderr.read(&mut stderr_buffer), if !is_stderr_eof => {
+                let n = res?;
+                if n == 0 {
                     trace!("program {} stderr is EOF", pid);
+                    stderr_lines.finish(&mut stderr_line_callback);
                     is_stderr_eof = true;
+                } else {
+                    stderr_lines.push(&stderr_buffer[..n], &mut stderr_line_callback);
                 }
             },
-            _ = tokio::time::sleep(Duration::from_millis(200)) => {
+            _ = &mut cancelled => {
+                termination = ProgramTermination::Cancelled;
+                trace!("program {} cancelled! killing process", pid);
+                child.kill().await?;
+                break;
+            },
+            _ = watchdog.tick() => {
                 let elapsed = start_time.elapsed();
-                if elapsed.as_millis() > timeout_millis {
-                    is_timeout = true;
+                if elapsed.as_millis() > limit.timeout_millis {
+                    termination = ProgramTermination::TimeLimitExceeded;
                     trace!("program {} timeout! killing process", pid);
                     child.kill().await?;
                     break;
                 }
+                if let Some(memory_limit) = limit.memory_limit_kib {
+                    let memory = resident_memory_kib(pid).unwrap_or(0);
+                    if memory > memory_limit {
+                        termination = ProgramTermination::MemoryLimitExceeded;
+                        trace!(
+                            "program {} uses {} KiB memory, exceeds limit {} KiB! killing process",
+                            pid,
+                            memory,
+                            memory_limit
+                        );
+                        child.kill().await?;
+                        break;
+                    }
+                }
             }
         }
     }
     output_file_writer.flush().await?;
     output_file_writer.shutdown().await?;
-    let exit_code = child.wait().await?.code().unwrap_or(-1);
-    trace!("program {} exit code: {}", pid, exit_code);
-    let filesize = tokio::fs::metadata(output_file.as_ref()).await?.len();
-    trace!("program {} output file size: {} bytes", pid, filesize);
+    let exit = child.wait().await?;
+    let exit_code = exit.exit_code;
+    trace!(
+        "program {} exit code: {}, usage: {:?}",
+        pid,
+        exit_code,
+        exit.usage
+    );
+    // the stdin may still be held open by a leftover descendant of the program
+    input_feeder.abort();
+    match input_feeder.await {
+        Ok(result) => trace!("redirect input {} bytes to process {}", result?, pid),
+        Err(e) if e.is_cancelled() => trace!("stop feeding input to process {}", pid),
+        Err(e) => return Err(e.into()),
+    }
+    if termination == ProgramTermination::Exited && exit.is_security_violation() {
+        trace!("program {} made a forbidden system call", pid);
+        termination = ProgramTermination::SecurityViolation;
+    }
+    if let (Some(memory_limit), Some(peak_memory)) =
+        (limit.memory_limit_kib, exit.usage.peak_memory_kib)
+    {
+        // the watchdog samples memory periodically and may miss a short spike,
+        // the peak reported by the kernel is authoritative
+        if termination == ProgramTermination::Exited && peak_memory as u64 > memory_limit {
+            trace!(
+                "program {} peak memory {} KiB exceeds limit {} KiB",
+                pid,
+                peak_memory,
+                memory_limit
+            );
+            termination = ProgramTermination::MemoryLimitExceeded;
+        }
+    }
+    read_program_output(
+        output_file.as_ref(),
+        exit_code,
+        termination,
+        exit.usage,
+        limit.timeout_millis as u32,
+    )
+    .await
+}
+
+/// Build the [`ProgramOutput`] of a finished program from the file its output was saved to,
+/// only the first 5MiB of the file is loaded and decoded lossily as a preview
+pub async fn read_program_output(
+    output_file: &Path,
+    exit_code: i32,
+    termination: ProgramTermination,
+    usage: ResourceUsage,
+    time_limit_millis: u32,
+) -> Result<ProgramOutput> {
+    let filesize = tokio::fs::metadata(output_file).await?.len();
+    trace!("output file {:?} size: {} bytes", output_file, filesize);
     if filesize > 5 * 1024 * 1024 {
         // file is greater than 5MiB
-        let file = tokio::fs::File::open(output_file.as_ref()).await?;
-        let mut content = String::with_capacity(5 * 1024 * 1024);
-        file.take(5 * 1024 * 1024)
-            .read_to_string(&mut content)
-            .await?;
+        let file = tokio::fs::File::open(output_file).await?;
+        let mut content = Vec::with_capacity(5 * 1024 * 1024);
+        file.take(5 * 1024 * 1024).read_to_end(&mut content).await?;
+        let content = String::from_utf8_lossy(&content).into_owned();
         Ok(ProgramOutput::Strip {
             exit_code,
             size: filesize as u32,
-            is_timeout,
+            termination,
+            usage,
+            time_limit_millis,
             content,
-            output_file: output_file.as_ref().to_path_buf(),
+            output_file: output_file.to_path_buf(),
         })
     } else {
-        let content = tokio::fs::read_to_string(output_file.as_ref()).await?;
+        let content = String::from_utf8_lossy(&tokio::fs::read(output_file).await?).into_owned();
         Ok(ProgramOutput::Full {
             exit_code,
-            is_timeout,
+            termination,
+            usage,
+            time_limit_millis,
             content,
-            output_file: output_file.as_ref().to_path_buf(),
+            output_file: output_file.to_path_buf(),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(chunks: &[&[u8]]) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut callback = |line: &str| lines.push(line.to_string());
+        let mut splitter = LineSplitter::default();
+        for chunk in chunks {
+            splitter.push(chunk, &mut callback);
+        }
+        splitter.finish(&mut callback);
+        lines
+    }
+
+    #[test]
+    fn test_split_lines_across_chunks() {
+        assert_eq!(split(&[b"1 2", b"\n3", b" 4\n"]), vec!["1 2", "3 4"]);
+    }
+
+    #[test]
+    fn test_split_last_line_without_newline() {
+        assert_eq!(split(&[b"a\r\nb"]), vec!["a", "b"]);
+        assert_eq!(split(&[b"a\n\n"]), vec!["a", ""]);
+    }
+
+    #[test]
+    fn test_split_invalid_utf8() {
+        assert_eq!(split(&[b"\xff\n"]), vec!["\u{fffd}"]);
+    }
+
+    #[cfg(unix)]
+    fn sh(script: &str) -> Command {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(script);
+        cmd
+    }
+
+    /// A token cancelled a moment after the program is launched
+    #[cfg(unix)]
+    fn cancel_later() -> CancelToken {
+        let cancel = CancelToken::new();
+        let token = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            token.cancel();
+        });
+        cancel
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_cancel_program() {
+        let work_dir =
+            std::env::temp_dir().join(format!("algorimejo-run-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&work_dir).await.unwrap();
+        let input_file = work_dir.join("case.in");
+        tokio::fs::write(&input_file, "").await.unwrap();
+        let output = launch_program(
+            sh("echo started; sleep 10"),
+            &input_file,
+            work_dir.join("case.out"),
+            RunLimit {
+                timeout_millis: 8000,
+                memory_limit_kib: None,
+                output_limit_bytes: None,
+            },
+            &cancel_later(),
+            |_| {},
+            |_| {},
+        )
+        .await
+        .unwrap();
+        let _ = std::fs::remove_dir_all(&work_dir);
+        assert_eq!(output.termination(), ProgramTermination::Cancelled);
+        assert!(output.usage().wall_time_millis < 8000);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_cancel_program_without_input() {
+        let output = launch_program_without_input(sh("sleep 10"), 8000, &cancel_later())
+            .await
+            .unwrap();
+        assert_eq!(output.termination, ProgramTermination::Cancelled);
+        assert!(output.usage.wall_time_millis < 8000);
+    }
+}
@@ -0,0 +1,557 @@
+//! Run untrusted programs in fresh user, mount and network namespaces with a seccomp filter.
+//! Everything outside the working directory is read-only and the network is unreachable.
+//! A program killed by the seccomp filter receives `SIGSYS`,
+//! which is reported as [`ProgramTermination::SecurityViolation`](crate::runner::run::ProgramTermination::SecurityViolation).
+use std::{path::Path, process::Command};
+
+use anyhow::Result;
+
+/// Restrict the command to the sandbox, `work_dir` is the only writable directory
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+pub fn apply(cmd: &mut Command, work_dir: &Path) -> Result<()> {
+    linux::apply(cmd, work_dir)
+}
+
+#[cfg(not(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+pub fn apply(_cmd: &mut Command, _work_dir: &Path) -> Result<()> {
+    Err(anyhow::anyhow!(
+        "Sandbox is only supported on Linux for x86_64 and aarch64"
+    ))
+}
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod linux {
+    use std::{
+        ffi::CString,
+        os::unix::{ffi::OsStrExt, process::CommandExt},
+        path::Path,
+        process::Command,
+    };
+
+    use anyhow::{anyhow, Result};
+
+    // not every version of libc exposes these, their values are part of the kernel ABI
+    const SYS_MOUNT_SETATTR: libc::c_long = 442;
+    const MOUNT_ATTR_RDONLY: u64 = 0x00000001;
+    const AT_RECURSIVE: libc::c_uint = 0x8000;
+
+    const BPF_LD_W_ABS: u16 = 0x20;
+    const BPF_JMP_JEQ_K: u16 = 0x15;
+    #[cfg(target_arch = "x86_64")]
+    const BPF_JMP_JGE_K: u16 = 0x35;
+    const BPF_JMP_JSET_K: u16 = 0x45;
+    const BPF_RET_K: u16 = 0x06;
+    const SECCOMP_RET_KILL_PROCESS: u32 = 0x80000000;
+    const SECCOMP_RET_ERRNO: u32 = 0x00050000;
+    const SECCOMP_RET_ALLOW: u32 = 0x7fff0000;
+    /// Offsets in `struct seccomp_data`, only the lower half of the first argument is loaded
+    const SECCOMP_DATA_NR: u32 = 0;
+    const SECCOMP_DATA_ARCH: u32 = 4;
+    const SECCOMP_DATA_ARG0: u32 = 16;
+    /// System calls of the x32 ABI share the architecture of x86_64 with this bit set
+    #[cfg(target_arch = "x86_64")]
+    const X32_SYSCALL_BIT: u32 = 0x40000000;
+    /// `clone3` passes its flags in a struct the filter can not read
+    const SYS_CLONE3: libc::c_long = 435;
+    /// Flags of `clone` creating new namespaces, `CLONE_NEWTIME` is only accepted by `clone3`
+    const CLONE_NAMESPACE_FLAGS: u32 = (libc::CLONE_NEWNS
+        | libc::CLONE_NEWUTS
+        | libc::CLONE_NEWIPC
+        | libc::CLONE_NEWUSER
+        | libc::CLONE_NEWPID
+        | libc::CLONE_NEWNET
+        | libc::CLONE_NEWCGROUP) as u32;
+
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: u32 = 0xc000003e;
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: u32 = 0xc00000b7;
+
+    #[repr(C)]
+    struct MountAttr {
+        attr_set: u64,
+        attr_clr: u64,
+        propagation: u64,
+        userns_fd: u64,
+    }
+
+    /// System calls a solution has no reason to make
+    const FORBIDDEN_SYSCALLS: &[libc::c_long] = &[
+        libc::SYS_socket,
+        libc::SYS_socketpair,
+        libc::SYS_connect,
+        libc::SYS_bind,
+        libc::SYS_listen,
+        libc::SYS_accept,
+        libc::SYS_accept4,
+        libc::SYS_ptrace,
+        libc::SYS_process_vm_readv,
+        libc::SYS_process_vm_writev,
+        libc::SYS_mount,
+        libc::SYS_umount2,
+        libc::SYS_pivot_root,
+        libc::SYS_chroot,
+        libc::SYS_unshare,
+        libc::SYS_setns,
+        libc::SYS_reboot,
+        libc::SYS_kexec_load,
+        libc::SYS_init_module,
+        libc::SYS_finit_module,
+        libc::SYS_delete_module,
+        libc::SYS_bpf,
+        libc::SYS_perf_event_open,
+        libc::SYS_keyctl,
+        libc::SYS_add_key,
+        libc::SYS_request_key,
+        libc::SYS_userfaultfd,
+        libc::SYS_swapon,
+        libc::SYS_swapoff,
+        libc::SYS_acct,
+        libc::SYS_settimeofday,
+        libc::SYS_clock_settime,
+    ];
+
+    fn seccomp_filter() -> Vec<libc::sock_filter> {
+        let stmt = |code, k| libc::sock_filter {
+            code,
+            jt: 0,
+            jf: 0,
+            k,
+        };
+        let mut filter = vec![
+            stmt(BPF_LD_W_ABS, SECCOMP_DATA_ARCH),
+            // system calls of a foreign architecture could bypass the numbers below
+            libc::sock_filter {
+                code: BPF_JMP_JEQ_K,
+                jt: 1,
+                jf: 0,
+                k: AUDIT_ARCH,
+            },
+            stmt(BPF_RET_K, SECCOMP_RET_KILL_PROCESS),
+            stmt(BPF_LD_W_ABS, SECCOMP_DATA_NR),
+        ];
+        #[cfg(target_arch = "x86_64")]
+        filter.extend([
+            libc::sock_filter {
+                code: BPF_JMP_JGE_K,
+                jt: 0,
+                jf: 1,
+                k: X32_SYSCALL_BIT,
+            },
+            stmt(BPF_RET_K, SECCOMP_RET_KILL_PROCESS),
+        ]);
+        for nr in FORBIDDEN_SYSCALLS {
+            filter.push(libc::sock_filter {
+                code: BPF_JMP_JEQ_K,
+                jt: 0,
+                jf: 1,
+                k: *nr as u32,
+            });
+            filter.push(stmt(BPF_RET_K, SECCOMP_RET_KILL_PROCESS));
+        }
+        // the C library falls back to `clone` once `clone3` is reported as unimplemented
+        filter.extend([
+            libc::sock_filter {
+                code: BPF_JMP_JEQ_K,
+                jt: 0,
+                jf: 1,
+                k: SYS_CLONE3 as u32,
+            },
+            stmt(BPF_RET_K, SECCOMP_RET_ERRNO | libc::ENOSYS as u32),
+        ]);
+        // threads and child processes are fine, new namespaces are not
+        filter.extend([
+            libc::sock_filter {
+                code: BPF_JMP_JEQ_K,
+                jt: 0,
+                jf: 3,
+                k: libc::SYS_clone as u32,
+            },
+            stmt(BPF_LD_W_ABS, SECCOMP_DATA_ARG0),
+            libc::sock_filter {
+                code: BPF_JMP_JSET_K,
+                jt: 0,
+                jf: 1,
+                k: CLONE_NAMESPACE_FLAGS,
+            },
+            stmt(BPF_RET_K, SECCOMP_RET_KILL_PROCESS),
+        ]);
+        filter.push(stmt(BPF_RET_K, SECCOMP_RET_ALLOW));
+        filter
+    }
+
+    fn check(ret: libc::c_long) -> std::io::Result<()> {
+        if ret == -1 {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn write_file(path: &CString, content: &[u8]) -> std::io::Result<()> {
+        unsafe {
+            let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+            check(fd as libc::c_long)?;
+            let ret = libc::write(fd, content.as_ptr() as *const libc::c_void, content.len());
+            libc::close(fd);
+            check(ret as libc::c_long)
+        }
+    }
+
+    /// `mount_setattr` is only available since Linux 5.12
+    fn has_mount_setattr() -> bool {
+        // invalid arguments are rejected once the system call exists
+        let ret = unsafe {
+            libc::syscall(
+                SYS_MOUNT_SETATTR,
+                -1,
+                std::ptr::null::<libc::c_char>(),
+                0,
+                std::ptr::null::<MountAttr>(),
+                0,
+            )
+        };
+        !(ret == -1 && std::io::Error::last_os_error().raw_os_error() == Some(libc::ENOSYS))
+    }
+
+    /// Every mount point of the process, the sandbox starts with a copy of them
+    pub(super) fn mount_points() -> Result<Vec<CString>> {
+        let mountinfo = std::fs::read_to_string("/proc/self/mountinfo")?;
+        mountinfo
+            .lines()
+            .filter_map(|line| line.split(' ').nth(4))
+            .map(|path| {
+                CString::new(unescape_mount_point(path))
+                    .map_err(|_| anyhow!("Invalid mount point {:?}", path))
+            })
+            .collect()
+    }
+
+    /// Spaces, tabs, newlines and backslashes of a mount point are escaped as octal, like `\040`
+    pub(super) fn unescape_mount_point(path: &str) -> Vec<u8> {
+        let bytes = path.as_bytes();
+        let mut unescaped = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let code = bytes
+                .get(i + 1..i + 4)
+                .filter(|_| bytes[i] == b'\\')
+                .and_then(|digits| std::str::from_utf8(digits).ok())
+                .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+            match code {
+                Some(code) => {
+                    unescaped.push(code);
+                    i += 4;
+                }
+                None => {
+                    unescaped.push(bytes[i]);
+                    i += 1;
+                }
+            }
+        }
+        unescaped
+    }
+
+    /// Change the flags of the mount at `path` and every mount below it
+    fn mount_setattr(path: &CString, attr_set: u64, attr_clr: u64) -> std::io::Result<()> {
+        let attr = MountAttr {
+            attr_set,
+            attr_clr,
+            propagation: 0,
+            userns_fd: 0,
+        };
+        check(unsafe {
+            libc::syscall(
+                SYS_MOUNT_SETATTR,
+                libc::AT_FDCWD,
+                path.as_ptr(),
+                AT_RECURSIVE,
+                &attr as *const MountAttr,
+                std::mem::size_of::<MountAttr>(),
+            )
+        })
+    }
+
+    /// Change the read-only flag of the single mount at `path`, the fallback of `mount_setattr`
+    fn remount(path: &CString, read_only: bool) -> std::io::Result<()> {
+        unsafe {
+            let mut stat: libc::statvfs = std::mem::zeroed();
+            check(libc::statvfs(path.as_ptr(), &mut stat) as libc::c_long)?;
+            // the flags locked by the host have to be kept, otherwise the remount is refused,
+            // the access time flags are kept by the kernel when none of them is given
+            let mut flags = libc::MS_REMOUNT | libc::MS_BIND;
+            for (st_flag, ms_flag) in [
+                (libc::ST_NOSUID, libc::MS_NOSUID),
+                (libc::ST_NODEV, libc::MS_NODEV),
+                (libc::ST_NOEXEC, libc::MS_NOEXEC),
+            ] {
+                if stat.f_flag & st_flag != 0 {
+                    flags |= ms_flag;
+                }
+            }
+            if read_only {
+                flags |= libc::MS_RDONLY;
+            }
+            check(libc::mount(
+                std::ptr::null(),
+                path.as_ptr(),
+                std::ptr::null(),
+                flags,
+                std::ptr::null(),
+            ) as libc::c_long)
+        }
+    }
+
+    pub fn apply(cmd: &mut Command, work_dir: &Path) -> Result<()> {
+        // older kernels remount every mount point one by one instead
+        let mount_points = if has_mount_setattr() {
+            None
+        } else {
+            Some(mount_points()?)
+        };
+        apply_with(cmd, work_dir, mount_points)
+    }
+
+    /// `mount_points` is `None` if the mounts are changed by `mount_setattr`
+    pub(super) fn apply_with(
+        cmd: &mut Command,
+        work_dir: &Path,
+        mount_points: Option<Vec<CString>>,
+    ) -> Result<()> {
+        let work_dir = CString::new(work_dir.as_os_str().as_bytes())
+            .map_err(|_| anyhow!("Invalid working directory {:?}", work_dir))?;
+        let root = CString::new("/").unwrap();
+        let setgroups = CString::new("/proc/self/setgroups").unwrap();
+        let uid_map_file = CString::new("/proc/self/uid_map").unwrap();
+        let gid_map_file = CString::new("/proc/self/gid_map").unwrap();
+        // the ids are kept the same inside the namespace, so the files created are owned by the user
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let uid_map = format!("{} {} 1", uid, uid);
+        let gid_map = format!("{} {} 1", gid, gid);
+        let filter = seccomp_filter();
+
+        // runs between fork and exec, so nothing here may allocate
+        let setup = move || -> std::io::Result<()> {
+            unsafe {
+                check(
+                    libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNS | libc::CLONE_NEWNET)
+                        as libc::c_long,
+                )?;
+                write_file(&setgroups, b"deny")?;
+                write_file(&uid_map_file, uid_map.as_bytes())?;
+                write_file(&gid_map_file, gid_map.as_bytes())?;
+
+                // keep the mounts below from propagating back to the host
+                check(libc::mount(
+                    std::ptr::null(),
+                    root.as_ptr(),
+                    std::ptr::null(),
+                    libc::MS_REC | libc::MS_PRIVATE,
+                    std::ptr::null(),
+                ) as libc::c_long)?;
+                match &mount_points {
+                    None => mount_setattr(&root, MOUNT_ATTR_RDONLY, 0)?,
+                    Some(mount_points) => {
+                        for path in mount_points {
+                            match remount(path, true) {
+                                // hidden below another mount or not reachable by the user anyway
+                                Err(e)
+                                    if matches!(
+                                        e.raw_os_error(),
+                                        Some(libc::ENOENT | libc::EACCES)
+                                    ) => {}
+                                res => res?,
+                            }
+                        }
+                    }
+                }
+                // a bind mount copies the read-only flag, only that flag is cleared afterwards
+                // since the others may be locked by the host
+                check(libc::mount(
+                    work_dir.as_ptr(),
+                    work_dir.as_ptr(),
+                    std::ptr::null(),
+                    libc::MS_BIND | libc::MS_REC,
+                    std::ptr::null(),
+                ) as libc::c_long)?;
+                match &mount_points {
+                    None => mount_setattr(&work_dir, 0, MOUNT_ATTR_RDONLY)?,
+                    Some(_) => remount(&work_dir, false)?,
+                }
+                // the current directory still refers to the mount before the bind
+                check(libc::chdir(work_dir.as_ptr()) as libc::c_long)?;
+
+                check(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) as libc::c_long)?;
+                let prog = libc::sock_fprog {
+                    len: filter.len() as libc::c_ushort,
+                    filter: filter.as_ptr() as *mut libc::sock_filter,
+                };
+                check(libc::prctl(
+                    libc::PR_SET_SECCOMP,
+                    libc::SECCOMP_MODE_FILTER,
+                    &prog as *const libc::sock_fprog,
+                ) as libc::c_long)?;
+            }
+            Ok(())
+        };
+        unsafe {
+            cmd.pre_exec(setup);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(
+    test,
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod tests {
+    use super::*;
+    use crate::runner::{
+        cancel::CancelToken,
+        run::{launch_program, ProgramOutput, ProgramTermination, RunLimit},
+    };
+
+    /// The sandbox needs unprivileged user namespaces and the scripts need python3,
+    /// the tests are skipped on a host without either
+    fn is_supported() -> bool {
+        let mut cmd = Command::new("python3");
+        cmd.arg("-c").arg("pass");
+        let supported = apply(&mut cmd, &std::env::temp_dir()).is_ok()
+            && cmd.output().is_ok_and(|output| output.status.success());
+        if !supported {
+            eprintln!("skipped: python3 or unprivileged user namespaces are not available");
+        }
+        supported
+    }
+
+    /// Run a python script in the sandbox
+    async fn run_sandboxed(script: &str) -> ProgramOutput {
+        run_sandboxed_with(script, apply).await
+    }
+
+    async fn run_sandboxed_with(
+        script: &str,
+        apply: impl FnOnce(&mut Command, &Path) -> Result<()>,
+    ) -> ProgramOutput {
+        let work_dir =
+            std::env::temp_dir().join(format!("algorimejo-sandbox-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&work_dir).unwrap();
+        let input_file = work_dir.join("case.in");
+        std::fs::write(&input_file, "").unwrap();
+        let mut cmd = Command::new("python3");
+        cmd.arg("-c").arg(script).current_dir(&work_dir);
+        apply(&mut cmd, &work_dir).unwrap();
+        let output = launch_program(
+            cmd,
+            &input_file,
+            work_dir.join("case.out"),
+            RunLimit {
+                timeout_millis: 5000,
+                memory_limit_kib: None,
+                output_limit_bytes: None,
+            },
+            &CancelToken::new(),
+            |_| {},
+            |_| {},
+        )
+        .await
+        .unwrap();
+        let _ = std::fs::remove_dir_all(&work_dir);
+        output
+    }
+
+    #[test]
+    fn test_unescape_mount_point() {
+        assert_eq!(
+            linux::unescape_mount_point("/mnt/a\\040b\\134c"),
+            b"/mnt/a b\\c"
+        );
+        assert_eq!(linux::unescape_mount_point("/a\\9"), b"/a\\9");
+    }
+
+    #[tokio::test]
+    async fn test_read_only_remount() {
+        if !is_supported() {
+            return;
+        }
+        let escape =
+            std::env::temp_dir().join(format!("algorimejo-escape-{}", uuid::Uuid::new_v4()));
+        let script = format!(
+            "open('scratch', 'w').write('x')\n\
+             try:\n    open({:?}, 'w')\nexcept OSError:\n    pass\nelse:\n    exit(1)",
+            escape
+        );
+        // the fallback for kernels without mount_setattr
+        let output = run_sandboxed_with(&script, |cmd, work_dir| {
+            linux::apply_with(cmd, work_dir, Some(linux::mount_points()?))
+        })
+        .await;
+        assert_eq!(output.termination(), ProgramTermination::Exited);
+        assert_eq!(output.exit_code(), 0);
+        assert!(!escape.exists());
+    }
+
+    #[tokio::test]
+    async fn test_forbidden_syscall() {
+        if !is_supported() {
+            return;
+        }
+        let output = run_sandboxed("import socket; socket.socket()").await;
+        assert_eq!(output.termination(), ProgramTermination::SecurityViolation);
+    }
+
+    #[tokio::test]
+    async fn test_clone_namespace() {
+        if !is_supported() {
+            return;
+        }
+        let script = format!(
+            "import ctypes; ctypes.CDLL(None).syscall({}, {}, 0, 0, 0, 0)",
+            libc::SYS_clone,
+            libc::CLONE_NEWUSER | libc::SIGCHLD
+        );
+        let output = run_sandboxed(&script).await;
+        assert_eq!(output.termination(), ProgramTermination::SecurityViolation);
+
+        // a child process without new namespaces is fine
+        let output =
+            run_sandboxed("import os\npid = os.fork()\npid or os._exit(0)\nos.waitpid(pid, 0)")
+                .await;
+        assert_eq!(output.termination(), ProgramTermination::Exited);
+        assert_eq!(output.exit_code(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_clone3_unimplemented() {
+        if !is_supported() {
+            return;
+        }
+        let script = format!(
+            "import ctypes, sys; libc = ctypes.CDLL(None, use_errno=True); \
+             sys.exit(libc.syscall(435, 0, 0) != -1 or ctypes.get_errno() != {})",
+            libc::ENOSYS
+        );
+        let output = run_sandboxed(&script).await;
+        assert_eq!(output.termination(), ProgramTermination::Exited);
+        assert_eq!(output.exit_code(), 0);
+
+        // threads are still created with the fallback to clone
+        let output = run_sandboxed(
+            "import threading; t = threading.Thread(target=print); t.start(); t.join()",
+        )
+        .await;
+        assert_eq!(output.termination(), ProgramTermination::Exited);
+        assert_eq!(output.exit_code(), 0);
+    }
+}
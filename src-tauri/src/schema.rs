@@ -31,6 +31,26 @@ diesel::table! {
         modified_datetime -> Timestamp,
         time_limit -> Integer,
         memory_limit -> Integer,
+        interactive -> Bool,
+        interactor -> Nullable<Text>,
+        input_file -> Nullable<Text>,
+        output_file -> Nullable<Text>,
+        validator -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    runs (id) {
+        id -> Text,
+        solution_id -> Text,
+        testcase_id -> Text,
+        source_hash -> Text,
+        verdict -> Text,
+        time_millis -> Nullable<Integer>,
+        memory_kib -> Nullable<Integer>,
+        checker_message -> Nullable<Text>,
+        output_file -> Nullable<Text>,
+        create_datetime -> Timestamp,
     }
 }
 
@@ -51,11 +71,18 @@ diesel::table! {
         problem_id -> Text,
         input_document_id -> Text,
         answer_document_id -> Text,
+        generator -> Nullable<Text>,
+        generator_args -> Nullable<Text>,
+        reference -> Nullable<Text>,
     }
 }
 
 diesel::joinable!(checker -> documents (document_id));
+diesel::joinable!(runs -> solutions (solution_id));
+diesel::joinable!(runs -> test_cases (testcase_id));
 diesel::joinable!(solutions -> problems (problem_id));
 diesel::joinable!(test_cases -> problems (problem_id));
 
-diesel::allow_tables_to_appear_in_same_query!(checker, documents, problems, solutions, test_cases,);
+diesel::allow_tables_to_appear_in_same_query!(
+    checker, documents, problems, runs, solutions, test_cases,
+);
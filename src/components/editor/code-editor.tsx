@@ -1,5 +1,9 @@
-import type { Extension } from "@codemirror/state"
+import type { Diagnostic as LintDiagnostic } from "@codemirror/lint"
+import type { Extension, Text } from "@codemirror/state"
+import type { AlgorimejoEvents } from "@/lib/algorimejo/events"
+import type { Diagnostic } from "@/lib/client"
 import { indentWithTab } from "@codemirror/commands"
+import { setDiagnostics } from "@codemirror/lint"
 import { Compartment, EditorState } from "@codemirror/state"
 import { EditorView, keymap } from "@codemirror/view"
 import * as log from "@tauri-apps/plugin-log"
@@ -145,6 +149,30 @@ export function CodeEditorSuspend({ className,	documentID,	language = "Text",	te
 	)
 }
 
+const lintSeverityOf = {
+	Error: "error",
+	Warning: "warning",
+	Note: "info",
+} as const
+
+/** Compiler diagnostics in the document, each one underlines the word at its position */
+function toLintDiagnostics(doc: Text, diagnostics: Diagnostic[]): LintDiagnostic[] {
+	return diagnostics
+		.filter(diagnostic => diagnostic.file === null && diagnostic.line <= doc.lines)
+		.map((diagnostic) => {
+			const line = doc.line(Math.max(diagnostic.line, 1))
+			const from = Math.min(line.from + Math.max((diagnostic.column ?? 1) - 1, 0), line.to)
+			const word = /^\w+/.exec(doc.sliceString(from, line.to))
+			return {
+				from,
+				to: word ? from + word[0].length : Math.min(from + 1, line.to),
+				severity: lintSeverityOf[diagnostic.severity],
+				message: diagnostic.message,
+				source: "compiler",
+			}
+		})
+}
+
 function CodeEditor({
 	className,
 	language = "Text",
@@ -194,6 +222,27 @@ function CodeEditor({
 		})
 		log.trace("dispatch reconfigure effects to codemirror editor")
 	}, [externalExtension, externalExtensionCompartment])
+	useEffect(() => {
+		const listener = (event: AlgorimejoEvents["compileDiagnostics"]) => {
+			const view = viewRef.current
+			if (!view || event.documentID !== documentID)
+				return
+			const diagnostics = toLintDiagnostics(view.state.doc, event.diagnostics)
+			view.dispatch(setDiagnostics(view.state, diagnostics))
+			// jump to the first error
+			const error = diagnostics.find(diagnostic => diagnostic.severity === "error")
+			if (error) {
+				view.dispatch({
+					selection: { anchor: error.from },
+					effects: EditorView.scrollIntoView(error.from, { y: "center" }),
+				})
+			}
+		}
+		algorimejo.events.on("compileDiagnostics", listener)
+		return () => {
+			algorimejo.events.off("compileDiagnostics", listener)
+		}
+	}, [documentID])
 
 	return (
 		<div
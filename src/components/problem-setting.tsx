@@ -1,4 +1,4 @@
-import type { Problem } from "@/lib/client"
+import type { Checker, Problem } from "@/lib/client"
 import { zodResolver } from "@hookform/resolvers/zod"
 import { useForm } from "react-hook-form"
 import { toast } from "react-toastify"
@@ -8,8 +8,10 @@ import { Button } from "@/components/ui/button"
 import { Form, FormControl, FormField, FormItem, FormLabel, FormMessage } from "@/components/ui/form"
 import { Input } from "@/components/ui/input"
 import { Skeleton } from "@/components/ui/skeleton"
+import { Switch } from "@/components/ui/switch"
 import { Textarea } from "@/components/ui/textarea"
 import { useCheckerNames } from "@/hooks/use-checker-names"
+import { useCheckers } from "@/hooks/use-checkers"
 import { useProblem } from "@/hooks/use-problem"
 import { useProblemChangeset } from "@/hooks/use-problem-changeset"
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from "./ui/select"
@@ -22,14 +24,20 @@ const problemSettingFormSchema = z.object({
 	checker: z.string(),
 	time_limit: z.number(),
 	memory_limit: z.number(),
+	interactive: z.boolean(),
+	interactor: z.string().optional(),
+	input_file: z.string().optional(),
+	output_file: z.string().optional(),
+	validator: z.string().optional(),
 })
 
 interface ProblemSettingContentProps extends ProblemSettingProps {
 	problemData: Problem
 	availableCheckerNames: string[]
+	availableCheckers: Checker[]
 }
 
-export function ProblemSettingContent({ problemID, problemData, availableCheckerNames, onCancel, onSubmitCompleted }: ProblemSettingContentProps) {
+export function ProblemSettingContent({ problemID, problemData, availableCheckerNames, availableCheckers, onCancel, onSubmitCompleted }: ProblemSettingContentProps) {
 	const form = useForm<z.infer<typeof problemSettingFormSchema>>({
 		resolver: zodResolver(problemSettingFormSchema),
 		defaultValues: {
@@ -40,8 +48,14 @@ export function ProblemSettingContent({ problemID, problemData, availableChecker
 			checker: problemData.checker ?? undefined,
 			time_limit: problemData.time_limit,
 			memory_limit: problemData.memory_limit,
+			interactive: problemData.interactive,
+			interactor: problemData.interactor ?? undefined,
+			input_file: problemData.input_file ?? undefined,
+			output_file: problemData.output_file ?? undefined,
+			validator: problemData.validator ?? undefined,
 		},
 	})
+	const interactive = form.watch("interactive")
 	const problemChangesetMutation = useProblemChangeset()
 	function handleSubmit(data: z.infer<typeof problemSettingFormSchema>) {
 		const convertNullIfEmpty = (value: string | undefined | null) => {
@@ -63,6 +77,11 @@ export function ProblemSettingContent({ problemID, problemData, availableChecker
 				checker: data.checker,
 				time_limit: data.time_limit,
 				memory_limit: data.memory_limit,
+				interactive: data.interactive,
+				interactor: convertNullIfEmpty(data.interactor),
+				input_file: convertNullIfEmpty(data.input_file),
+				output_file: convertNullIfEmpty(data.output_file),
+				validator: convertNullIfEmpty(data.validator),
 			},
 		}, {
 			onSuccess: () => {
@@ -164,6 +183,83 @@ export function ProblemSettingContent({ problemID, problemData, availableChecker
 												</SelectItem>
 											))
 										}
+										{
+											availableCheckers.map(checker => (
+												<SelectItem key={checker.id} value={checker.id}>
+													{checker.name}
+												</SelectItem>
+											))
+										}
+									</SelectContent>
+								</Select>
+							</FormItem>
+						)
+					}}
+				/>
+				<FormField
+					control={form.control}
+					name="interactive"
+					render={({ field }) => {
+						return (
+							<FormItem className="flex items-center justify-between">
+								<FormLabel className="font-medium">Interactive</FormLabel>
+								<FormControl>
+									<Switch checked={field.value} onCheckedChange={field.onChange} />
+								</FormControl>
+							</FormItem>
+						)
+					}}
+				/>
+				{interactive && (
+					<FormField
+						control={form.control}
+						name="interactor"
+						render={({ field }) => {
+							return (
+								<FormItem>
+									<FormLabel className="font-medium">Interactor</FormLabel>
+									<Select onValueChange={field.onChange} defaultValue={field.value}>
+										<FormControl>
+											<SelectTrigger>
+												<SelectValue placeholder="Select the interactor program" />
+											</SelectTrigger>
+										</FormControl>
+										<SelectContent>
+											{
+												availableCheckers.map(interactor => (
+													<SelectItem key={interactor.id} value={interactor.id}>
+														{interactor.name}
+													</SelectItem>
+												))
+											}
+										</SelectContent>
+									</Select>
+								</FormItem>
+							)
+						}}
+					/>
+				)}
+				<FormField
+					control={form.control}
+					name="validator"
+					render={({ field }) => {
+						return (
+							<FormItem>
+								<FormLabel className="font-medium">Validator</FormLabel>
+								<Select onValueChange={field.onChange} defaultValue={field.value}>
+									<FormControl>
+										<SelectTrigger>
+											<SelectValue placeholder="Select the validator program" />
+										</SelectTrigger>
+									</FormControl>
+									<SelectContent>
+										{
+											availableCheckers.map(validator => (
+												<SelectItem key={validator.id} value={validator.id}>
+													{validator.name}
+												</SelectItem>
+											))
+										}
 									</SelectContent>
 								</Select>
 							</FormItem>
@@ -202,6 +298,38 @@ export function ProblemSettingContent({ problemID, problemData, availableChecker
 						}}
 					/>
 				</div>
+				<div className="grid grid-cols-1 gap-2 md:grid-cols-2">
+					<FormField
+						control={form.control}
+						name="input_file"
+						render={({ field }) => {
+							return (
+								<FormItem>
+									<FormLabel className="font-medium">Input File</FormLabel>
+									<FormControl>
+										<Input {...field} className="w-full" placeholder="stdin" />
+									</FormControl>
+									<FormMessage />
+								</FormItem>
+							)
+						}}
+					/>
+					<FormField
+						control={form.control}
+						name="output_file"
+						render={({ field }) => {
+							return (
+								<FormItem>
+									<FormLabel className="font-medium">Output File</FormLabel>
+									<FormControl>
+										<Input {...field} className="w-full" placeholder="stdout" />
+									</FormControl>
+									<FormMessage />
+								</FormItem>
+							)
+						}}
+					/>
+				</div>
 
 				<div className="flex justify-end gap-2 pt-2">
 					<Button type="button" onClick={onCancel} variant="outline">Cancel</Button>
@@ -220,13 +348,17 @@ interface ProblemSettingProps {
 export function ProblemSetting({ problemID, ...props }: ProblemSettingProps) {
 	const problemData = useProblem(problemID)
 	const checkerNames = useCheckerNames()
+	const checkers = useCheckers()
 	if (problemData.status === "error") {
 		return <ErrorLabel message={problemData.error} location="get problem data" />
 	}
 	else if (checkerNames.status === "error") {
 		return <ErrorLabel message={checkerNames.error} location="get available checkers" />
 	}
-	else if (problemData.status === "pending" || checkerNames.status === "pending") {
+	else if (checkers.status === "error") {
+		return <ErrorLabel message={checkers.error} location="get available checker programs" />
+	}
+	else if (problemData.status === "pending" || checkerNames.status === "pending" || checkers.status === "pending") {
 		return (
 			<div>
 				<Skeleton className="h-10 w-full" />
@@ -234,5 +366,5 @@ export function ProblemSetting({ problemID, ...props }: ProblemSettingProps) {
 			</div>
 		)
 	}
-	return <ProblemSettingContent problemID={problemID} problemData={problemData.data} availableCheckerNames={checkerNames.data} {...props} />
+	return <ProblemSettingContent problemID={problemID} problemData={problemData.data} availableCheckerNames={checkerNames.data} availableCheckers={checkers.data} {...props} />
 }
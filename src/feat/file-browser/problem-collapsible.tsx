@@ -102,6 +102,7 @@ export function ProblemCollapsible({
 					checker: null,
 					time_limit: null,
 					memory_limit: null,
+					interactive: null,
 				},
 			},
 			{
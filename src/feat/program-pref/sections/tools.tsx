@@ -47,6 +47,18 @@ export function ToolsSection() {
 					onBlur={applyCompetitiveCompanionAddr}
 				/>
 			</PrefsItem>
+			<PrefsItem name="Terminal Command" description="The terminal emulator running a detached program, which is appended to the command. For example `x-terminal-emulator -e`, `kitty` or `alacritty -e`. Leave it empty to open a new console on Windows.">
+				<Input
+					value={changeset.terminal_command}
+					placeholder="x-terminal-emulator -e"
+					onChange={(e) => {
+						updateChangeset((draft) => {
+							draft.terminal_command = e.target.value
+						}, false)
+					}}
+					onBlur={() => applyChangeset()}
+				/>
+			</PrefsItem>
 		</PrefsSection>
 	)
 }
@@ -1,6 +1,6 @@
 import type { TestcaseItemRef } from "./testcase-item"
 import type { TabInstance } from "@/lib/algorimejo/tab-manager"
-import type { Problem, TestCase } from "@/lib/client"
+import type { Problem, ProgramOutput, TestCase } from "@/lib/client"
 import type { RunTestResultStatus } from "@/lib/runner"
 import * as log from "@tauri-apps/plugin-log"
 import { debounce } from "lodash/fp"
@@ -16,7 +16,7 @@ import { ErrorLabel } from "@/components/error-label"
 import { ProblemSetting } from "@/components/problem-setting"
 import { Button } from "@/components/ui/button"
 import { Dialog, DialogContent } from "@/components/ui/dialog"
-import { DropdownMenu, DropdownMenuContent, DropdownMenuItem, DropdownMenuTrigger } from "@/components/ui/dropdown-menu"
+import { DropdownMenu, DropdownMenuContent, DropdownMenuItem, DropdownMenuLabel, DropdownMenuRadioGroup, DropdownMenuRadioItem, DropdownMenuSeparator, DropdownMenuTrigger } from "@/components/ui/dropdown-menu"
 import { ScrollArea } from "@/components/ui/scroll-area"
 import { Skeleton } from "@/components/ui/skeleton"
 import { useLanguage } from "@/hooks/use-language"
@@ -24,7 +24,8 @@ import { useProblem } from "@/hooks/use-problem"
 import { useSolution } from "@/hooks/use-solution"
 import { useTestcaseCreator } from "@/hooks/use-testcase-creator"
 import { useTestcases } from "@/hooks/use-testcases"
-import { runProgramDetached, runTestcase, runTestStatusToColor } from "@/lib/runner"
+import { commands } from "@/lib/client"
+import { reportCompileDiagnostics, runAllTestcases, runProgramDetached, runTestcase, runTestStatusToColor } from "@/lib/runner"
 import { solutionEditorPageDataSchema } from "../editor/schema"
 import { TestcaseItem } from "./testcase-item"
 
@@ -117,11 +118,13 @@ function TestcaseList({ problem, testcases, solutionID }: TestcaseListProps) {
 			})
 			.exhaustive()
 	}, testcases.map(() => "UNRUN" as RunTestResultStatus))
+	const [itemsUsage, setItemsUsage] = useState<(Pick<ProgramOutput, "usage" | "time_limit_millis"> | undefined)[]>([])
 	const itemsRef = useRef<TestcaseItemRef[]>([])
 
 	useEffect(() => {
 		itemsRef.current = itemsRef.current.slice(0, testcases.length)
 		dispatchItemsStatus({ type: "reset", length: testcases.length })
+		setItemsUsage([])
 	}, [testcases])
 
 	const panelRef = useRef<HTMLDivElement>(null)
@@ -161,6 +164,10 @@ function TestcaseList({ problem, testcases, solutionID }: TestcaseListProps) {
 		enabled: !!solution.data,
 		language: solution.data?.language,
 	})
+	const [selectedProfile, setSelectedProfile] = useState<string | null>(null)
+	const compileProfiles = Object.keys(languageItem.data?.compile_profiles ?? {})
+	// the language of the solution may be changed, its profiles along with it
+	const profile = selectedProfile !== null && compileProfiles.includes(selectedProfile) ? selectedProfile : null
 
 	const handleRunTestcase = useCallback(async (testcase: TestCase, index: number) => {
 		if (!solution.data) {
@@ -173,15 +180,13 @@ function TestcaseList({ problem, testcases, solutionID }: TestcaseListProps) {
 		}
 		const tag = `tt-${testcase.id}`
 		dispatchItemsStatus({ type: "set", index, status: "PD" })
+		setItemsUsage(usage => usage.map((u, i) => i === index ? undefined : u))
 		itemsRef.current[index]?.clearOutput()
 		const info = await runTestcase({
 			tag,
-			testcaseInputDocID: testcase.input_document_id,
-			testcaseOutputDocID: testcase.answer_document_id,
-			solutionDocID: solution.data.document!.id,
-			checkerName: problem.checker ?? "wcmp",
-			language: languageItem.data,
-			runTimeout: problem.time_limit,
+			solutionID: solution.data.id,
+			testcaseID: testcase.id,
+			profile,
 			programOutputListener: (line, ty) => {
 				if (ty === "stdout") {
 					itemsRef.current[index]?.appendOutput(`${line}\n`)
@@ -189,14 +194,135 @@ function TestcaseList({ problem, testcases, solutionID }: TestcaseListProps) {
 			},
 		})
 		dispatchItemsStatus({ type: "set", index, status: info.result })
+		if (solution.data.document) {
+			reportCompileDiagnostics(solution.data.document.id, info)
+		}
+		if ("output" in info) {
+			setItemsUsage((usage) => {
+				const newUsage = [...usage]
+				newUsage[index] = info.output
+				return newUsage
+			})
+		}
 		log.trace(`testcase ${tag} result: ${JSON.stringify(info)}`)
-	}, [languageItem.data, problem.checker, problem.time_limit, solution.data])
+	}, [languageItem.data, solution.data, profile])
 
-	const handleRunAllTestcases = useCallback(() => {
+	const handleRunAllTestcases = useCallback(async () => {
+		if (!solution.data) {
+			toast.error("Solution is not loaded, please wait for a moment. If it still not loaded, please report this issue.")
+			return
+		}
+		const tag = `ta-${solution.data.id}`
+		const indexOf = new Map(testcases.map((testcase, index) => [testcase.id, index]))
 		for (let i = 0; i < testcases.length; i++) {
-			handleRunTestcase(testcases[i], i)
+			dispatchItemsStatus({ type: "set", index: i, status: "PD" })
+			itemsRef.current[i]?.clearOutput()
+		}
+		setItemsUsage([])
+		// the solution is compiled once, so is the verdict of the compilation
+		let isCompileReported = false
+		try {
+			await runAllTestcases({
+				tag,
+				solutionID: solution.data.id,
+				profile,
+				onProgress: (testcaseID, progress) => {
+					const index = indexOf.get(testcaseID)
+					if (index === undefined)
+						return
+					match(progress)
+						.with({ type: "Stdout" }, ({ line }) => itemsRef.current[index]?.appendOutput(`${line}\n`))
+						.with({ type: "Stderr" }, () => {})
+						.with({ type: "Judged" }, ({ verdict }) => {
+							dispatchItemsStatus({ type: "set", index, status: verdict.result })
+							// a crash is reported once known, a backtrace is captured after the other testcases are judged
+							const isCrashed = verdict.result === "RE" && (verdict.sanitizer !== null || verdict.backtrace !== null)
+							if ((!isCompileReported || isCrashed) && solution.data.document) {
+								reportCompileDiagnostics(solution.data.document.id, verdict)
+								isCompileReported = true
+							}
+							if ("output" in verdict) {
+								setItemsUsage((usage) => {
+									const newUsage = [...usage]
+									newUsage[index] = verdict.output
+									return newUsage
+								})
+							}
+							log.trace(`testcase ${testcaseID} result: ${JSON.stringify(verdict)}`)
+						})
+						.with({ type: "Failed" }, ({ error }) => {
+							dispatchItemsStatus({ type: "set", index, status: "UKE" })
+							log.error(`testcase ${testcaseID} failed: ${error}`)
+						})
+						.exhaustive()
+				},
+			})
+		}
+		catch (e) {
+			dispatchItemsStatus({ type: "reset", length: testcases.length })
+			toast.error(e instanceof Error ? e.message : (e as string))
+		}
+	}, [solution.data, testcases, profile])
+
+	const handleCancelRunning = useCallback(async () => {
+		const tags = testcases.map(testcase => `tt-${testcase.id}`)
+		if (solution.data) {
+			tags.push(`ta-${solution.data.id}`)
+		}
+		const cancelled = await Promise.all(tags.map(tag => commands.cancelTask(tag)))
+		if (!cancelled.includes(true)) {
+			toast.info("No running testcase to cancel")
+		}
+	}, [solution.data, testcases])
+
+	const handleValidateTestcases = useCallback(async () => {
+		if (!problem.validator) {
+			toast.error("No validator is set for this problem")
+			return
+		}
+		try {
+			const validations = await commands.validateTestcases(`tv-${problem.id}`, problem.id)
+			const failures = validations
+				.map(({ validation }, index) => ({ validation, index }))
+				.filter(({ validation }) => validation.result !== "VALID")
+			if (failures.length === 0) {
+				toast.success("All testcase inputs are valid")
+				return
+			}
+			for (const { validation, index } of failures) {
+				const message = "validator_message" in validation ? validation.validator_message : validation.result
+				toast.error(`Testcase #${index + 1}: ${message}`)
+			}
+		}
+		catch (e) {
+			toast.error(e instanceof Error ? e.message : (e as string))
+		}
+	}, [problem.id, problem.validator])
+
+	const handleRegenerateTestcase = useCallback(async (testcase: TestCase, index: number) => {
+		try {
+			const outcome = await commands.regenerateTestcase(`tg-${testcase.id}`, testcase.id)
+			return match(outcome)
+				.with({ type: "Generated" }, () => {
+					toast.success(`Testcase #${index + 1} is regenerated`)
+					return true
+				})
+				.with({ type: "GeneratorFailed" }, () => {
+					toast.error(`Testcase #${index + 1}: the generator did not exit normally`)
+					return false
+				})
+				.with({ type: "ReferenceFailed" }, () => {
+					toast.error(`Testcase #${index + 1}: the reference solution did not exit normally`)
+					return false
+				})
+				.with({ type: "Cancelled" }, () => false)
+				.exhaustive()
+		}
+		catch (e) {
+			toast.error(e instanceof Error ? e.message : (e as string))
+			return false
 		}
-	}, [handleRunTestcase, testcases])
+	}, [])
 
 	const handleRunTestcaseDetached = useCallback(async () => {
 		if (!solution.data) {
@@ -210,11 +336,15 @@ function TestcaseList({ problem, testcases, solutionID }: TestcaseListProps) {
 		const tag = `sol-${solution.data.id}`
 		const info = await runProgramDetached({
 			tag,
-			solutionDocID: solution.data.document!.id,
+			solutionID: solution.data.id,
+			profile,
 			language: languageItem.data,
 		})
+		if (solution.data.document) {
+			reportCompileDiagnostics(solution.data.document.id, info)
+		}
 		log.trace(`run (detached) ${tag} result: ${JSON.stringify(info)}`)
-	}, [solution, languageItem])
+	}, [solution, languageItem, profile])
 	return (
 		<div className="flex h-full flex-col p-2 pr-0" ref={panelRef}>
 			<Dialog open={isEditingProblemOptions} onOpenChange={setIsEditingProblemOptions}>
@@ -239,7 +369,10 @@ function TestcaseList({ problem, testcases, solutionID }: TestcaseListProps) {
 								index={index}
 								key={testcase.id}
 								status={itemsStatus[index]}
+								usage={itemsUsage[index]}
+								solutions={problem.solutions}
 								onRunTestcase={testcase => handleRunTestcase(testcase, index)}
+								onRegenerateTestcase={testcase => handleRegenerateTestcase(testcase, index)}
 							/>
 						))}
 					</ul>
@@ -275,6 +408,27 @@ function TestcaseList({ problem, testcases, solutionID }: TestcaseListProps) {
 							<DropdownMenuItem onClick={handleRunTestcaseDetached}>
 								Run Detached
 							</DropdownMenuItem>
+							<DropdownMenuItem onClick={handleCancelRunning}>
+								Cancel Running
+							</DropdownMenuItem>
+							<DropdownMenuItem onClick={handleValidateTestcases}>
+								Validate Inputs
+							</DropdownMenuItem>
+							{compileProfiles.length > 0 && (
+								<>
+									<DropdownMenuSeparator />
+									<DropdownMenuLabel>Compile Profile</DropdownMenuLabel>
+									<DropdownMenuRadioGroup
+										value={profile ?? ""}
+										onValueChange={value => setSelectedProfile(value === "" ? null : value)}
+									>
+										<DropdownMenuRadioItem value="">Default</DropdownMenuRadioItem>
+										{compileProfiles.map(name => (
+											<DropdownMenuRadioItem key={name} value={name}>{name}</DropdownMenuRadioItem>
+										))}
+									</DropdownMenuRadioGroup>
+								</>
+							)}
 						</DropdownMenuContent>
 					</DropdownMenu>
 				</span>
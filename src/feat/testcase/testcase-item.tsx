@@ -1,20 +1,27 @@
 import type { CodeMirrorTextareaRef } from "@/components/editor/textarea"
-import type { TestCase } from "@/lib/client"
+import type { ProgramOutput, Solution, TestCase } from "@/lib/client"
 import type { RunTestResultStatus } from "@/lib/runner"
-import { LucideBugPlay, LucidePlay, LucideTrash } from "lucide-react"
-import { forwardRef, useImperativeHandle, useRef } from "react"
+import { LucideBugPlay, LucidePlay, LucideRefreshCw, LucideTrash, LucideWand2 } from "lucide-react"
+import { forwardRef, useImperativeHandle, useRef, useState } from "react"
 import { CodeEditor } from "@/components/editor"
 import { CodeMirrorTextarea } from "@/components/editor/textarea"
 import { Button } from "@/components/ui/button"
-import { runTestStatusToColor } from "@/lib/runner"
+import { Dialog, DialogContent } from "@/components/ui/dialog"
+import { formatResourceUsage, runTestStatusToColor } from "@/lib/runner"
 import { cn } from "@/lib/utils"
+import { TestcaseRecipe } from "./testcase-recipe"
 
 interface TestcaseItemProps {
 	testcase: TestCase
 	index: number
 	colsNum: number
 	status: RunTestResultStatus
+	/** Resources used by the last run, along with the time limit it is run with */
+	usage?: Pick<ProgramOutput, "usage" | "time_limit_millis">
+	solutions: Solution[]
 	onRunTestcase?: (testcase: TestCase) => void
+	/** Resolves to true if the input and the answer are regenerated */
+	onRegenerateTestcase?: (testcase: TestCase) => Promise<boolean>
 }
 export interface TestcaseItemRef {
 	appendOutput: (output: string) => void
@@ -22,8 +29,11 @@ export interface TestcaseItemRef {
 }
 
 export const TestcaseItem = forwardRef<TestcaseItemRef, TestcaseItemProps>(
-	({ testcase, colsNum, index, status, onRunTestcase }, ref) => {
+	({ testcase, colsNum, index, status, usage, solutions, onRunTestcase, onRegenerateTestcase }, ref) => {
 		const outputRef = useRef<CodeMirrorTextareaRef | null>(null)
+		const [isEditingRecipe, setIsEditingRecipe] = useState(false)
+		// the editors load the documents again after they are regenerated
+		const [revision, setRevision] = useState(0)
 
 		useImperativeHandle(ref, () => ({
 			appendOutput: (output: string) => {
@@ -38,8 +48,24 @@ export const TestcaseItem = forwardRef<TestcaseItemRef, TestcaseItemProps>(
 			},
 		}))
 
+		async function handleRegenerate() {
+			if (await onRegenerateTestcase?.(testcase)) {
+				setRevision(revision => revision + 1)
+			}
+		}
+
 		return (
 			<li className="rounded-lg border p-3 shadow-sm transition-shadow hover:shadow">
+				<Dialog open={isEditingRecipe} onOpenChange={setIsEditingRecipe}>
+					<DialogContent>
+						<TestcaseRecipe
+							testcase={testcase}
+							solutions={solutions}
+							onCancel={() => setIsEditingRecipe(false)}
+							onSubmitCompleted={() => setIsEditingRecipe(false)}
+						/>
+					</DialogContent>
+				</Dialog>
 				<div className="mb-3 flex flex-col gap-2">
 					<div className="flex flex-wrap items-center gap-2">
 						<h3 className="text-base font-semibold">
@@ -52,6 +78,16 @@ export const TestcaseItem = forwardRef<TestcaseItemRef, TestcaseItemProps>(
 						>
 							{status}
 						</div>
+						{usage && (
+							<span className="text-xs text-muted-foreground">
+								{formatResourceUsage(usage.usage, usage.time_limit_millis)}
+							</span>
+						)}
+						{testcase.generator && (
+							<span className="truncate font-mono text-xs text-muted-foreground" title="Generator arguments">
+								{`gen ${testcase.generator_args ?? ""}`}
+							</span>
+						)}
 					</div>
 
 					<div className="flex items-center gap-1">
@@ -69,6 +105,26 @@ export const TestcaseItem = forwardRef<TestcaseItemRef, TestcaseItemProps>(
 						<Button variant="outline" size="sm" className="h-7 w-7 p-0">
 							<LucideBugPlay className="h-3 w-3" />
 						</Button>
+						<Button
+							variant="outline"
+							size="sm"
+							className="h-7 w-7 p-0"
+							title="Generator Recipe"
+							onClick={() => setIsEditingRecipe(true)}
+						>
+							<LucideWand2 className="h-3 w-3" />
+						</Button>
+						{testcase.generator && (
+							<Button
+								variant="outline"
+								size="sm"
+								className="h-7 w-7 p-0"
+								title="Regenerate"
+								onClick={handleRegenerate}
+							>
+								<LucideRefreshCw className="h-3 w-3" />
+							</Button>
+						)}
 					</div>
 				</div>
 
@@ -85,6 +141,7 @@ export const TestcaseItem = forwardRef<TestcaseItemRef, TestcaseItemProps>(
 						</div>
 						<div className="flex-1 overflow-hidden rounded-md border">
 							<CodeEditor
+								key={revision}
 								className="size-full min-h-24"
 								documentID={testcase.input_document_id}
 								language="Text"
@@ -99,6 +156,7 @@ export const TestcaseItem = forwardRef<TestcaseItemRef, TestcaseItemProps>(
 						</div>
 						<div className="flex-1 overflow-hidden rounded-md border">
 							<CodeEditor
+								key={revision}
 								className="size-full min-h-24"
 								documentID={testcase.answer_document_id}
 								language="Text"
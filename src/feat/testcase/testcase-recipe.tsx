@@ -0,0 +1,107 @@
+import type { Solution, TestCase } from "@/lib/client"
+import { useState } from "react"
+import { toast } from "react-toastify"
+import { Button } from "@/components/ui/button"
+import { Input } from "@/components/ui/input"
+import { Label } from "@/components/ui/label"
+import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from "@/components/ui/select"
+import { REMOVE_RECIPE_CHANGESET, useTestcaseChangeset } from "@/hooks/use-testcase-changeset"
+
+interface TestcaseRecipeProps {
+	testcase: TestCase
+	solutions: Solution[]
+	onCancel?: () => void
+	onSubmitCompleted?: () => void
+}
+
+export function TestcaseRecipe({ testcase, solutions, onCancel, onSubmitCompleted }: TestcaseRecipeProps) {
+	const [generator, setGenerator] = useState(testcase.generator ?? undefined)
+	const [generatorArgs, setGeneratorArgs] = useState(testcase.generator_args ?? "")
+	const [reference, setReference] = useState(testcase.reference ?? undefined)
+	const testcaseChangeset = useTestcaseChangeset(testcase.problem_id)
+
+	function handleSubmit() {
+		if (!generator || !reference) {
+			toast.error("Both the generator and the reference solution are required")
+			return
+		}
+		testcaseChangeset.mutate({
+			id: testcase.id,
+			changeset: {
+				generator,
+				generator_args: generatorArgs.trim() === "" ? null : generatorArgs,
+				reference,
+			},
+		}, {
+			onSuccess: () => onSubmitCompleted?.(),
+			onError: error => toast.error(error.message),
+		})
+	}
+
+	function handleRemove() {
+		testcaseChangeset.mutate({
+			id: testcase.id,
+			changeset: REMOVE_RECIPE_CHANGESET,
+		}, {
+			onSuccess: () => onSubmitCompleted?.(),
+			onError: error => toast.error(error.message),
+		})
+	}
+
+	return (
+		<div className="flex flex-col gap-4">
+			<div className="flex flex-col gap-2">
+				<Label className="font-medium">Generator</Label>
+				<Select onValueChange={setGenerator} defaultValue={generator}>
+					<SelectTrigger>
+						<SelectValue placeholder="Select the solution printing the input" />
+					</SelectTrigger>
+					<SelectContent>
+						{solutions.map(solution => (
+							<SelectItem key={solution.id} value={solution.id}>
+								{solution.name}
+							</SelectItem>
+						))}
+					</SelectContent>
+				</Select>
+			</div>
+			<div className="flex flex-col gap-2">
+				<Label className="font-medium">Arguments</Label>
+				<Input
+					placeholder="10 1000 --seed=3"
+					value={generatorArgs}
+					onChange={e => setGeneratorArgs(e.target.value)}
+				/>
+			</div>
+			<div className="flex flex-col gap-2">
+				<Label className="font-medium">Reference Solution</Label>
+				<Select onValueChange={setReference} defaultValue={reference}>
+					<SelectTrigger>
+						<SelectValue placeholder="Select the solution printing the answer" />
+					</SelectTrigger>
+					<SelectContent>
+						{solutions.map(solution => (
+							<SelectItem key={solution.id} value={solution.id}>
+								{solution.name}
+							</SelectItem>
+						))}
+					</SelectContent>
+				</Select>
+			</div>
+			<div className="flex justify-end gap-2">
+				{testcase.generator && (
+					<Button
+						variant="destructive"
+						className="mr-auto"
+						onClick={handleRemove}
+						disabled={testcaseChangeset.isPending}
+					>
+						Remove Recipe
+					</Button>
+				)}
+				<Button variant="outline" onClick={onCancel}>Cancel</Button>
+				<Button onClick={handleSubmit} disabled={testcaseChangeset.isPending}>Save</Button>
+			</div>
+		</div>
+	)
+}
@@ -1,4 +1,4 @@
-import type { LanguageBase, LanguageServerProtocolConnectionType } from "@/lib/client"
+import type { BuildStep, LanguageBase, LanguageServerProtocolConnectionType } from "@/lib/client"
 import { cloneDeep } from "lodash/fp"
 import { LucideCircleQuestionMark, LucideCopy, LucidePenBox, LucidePlusSquare, LucideSave, LucideSettings, LucideTextCursorInput, LucideTrash } from "lucide-react"
 import { useState } from "react"
@@ -14,6 +14,7 @@ import { ScrollArea } from "@/components/ui/scroll-area"
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from "@/components/ui/select"
 import { Separator } from "@/components/ui/separator"
 import { Skeleton } from "@/components/ui/skeleton"
+import { Switch } from "@/components/ui/switch"
 import { Textarea } from "@/components/ui/textarea"
 import { Tooltip, TooltipContent, TooltipTrigger } from "@/components/ui/tooltip"
 import { languageBaseValues, languageServerProtocolConnectionTypeValues } from "@/lib/client/type"
@@ -31,12 +32,18 @@ export function CompilerSection() {
 			draft.language![`Nova Lingvo ${uuid().substring(0, 4)}`] = {
 				base: "Cpp",
 				cmd_compile: "",
+				cmd_compile_debug: null,
+				compile_profiles: {},
+				build_steps: [],
 				cmd_run: "",
 				cmd_after_run: null,
 				cmd_before_run: null,
 				lsp: null,
 				lsp_connect: null,
 				initial_solution_content: null,
+				sandbox: false,
+				time_multiplier: 1,
+				time_extra_millis: 0,
 			}
 		})
 	}
@@ -57,6 +64,42 @@ export function CompilerSection() {
 			setSelectedLanguageName(newName)
 		}, true)
 	}
+	function handleAddCompileProfile() {
+		setChangeset((draft) => {
+			const language = draft.language![selectedLanguageName]!
+			language.compile_profiles = { ...language.compile_profiles, [`profile-${uuid().substring(0, 4)}`]: language.cmd_compile }
+		})
+	}
+	function handleRenameCompileProfile(name: string, newName: string) {
+		setChangeset((draft) => {
+			const language = draft.language![selectedLanguageName]!
+			// keep the order of the profiles while renaming
+			language.compile_profiles = Object.fromEntries(
+				Object.entries(language.compile_profiles ?? {}).map(([key, command]) => [key === name ? newName : key, command]),
+			)
+		})
+	}
+	function handleRemoveCompileProfile(name: string) {
+		setChangeset((draft) => {
+			delete draft.language![selectedLanguageName]!.compile_profiles?.[name]
+		})
+	}
+	function handleAddBuildStep() {
+		setChangeset((draft) => {
+			const language = draft.language![selectedLanguageName]!
+			language.build_steps = [...(language.build_steps ?? []), { name: `step-${uuid().substring(0, 4)}`, command: "", timeout_millis: null, env: {}, artifacts: [] }]
+		})
+	}
+	function handleUpdateBuildStep(index: number, recipe: (step: BuildStep) => void) {
+		setChangeset((draft) => {
+			recipe(draft.language![selectedLanguageName]!.build_steps![index]!)
+		})
+	}
+	function handleRemoveBuildStep(index: number) {
+		setChangeset((draft) => {
+			draft.language![selectedLanguageName]!.build_steps!.splice(index, 1)
+		})
+	}
 	const [newLanguageName, setNewLanguageName] = useState("")
 	function handleRenameLanguage() {
 		setChangeset((draft) => {
@@ -273,6 +316,65 @@ export function CompilerSection() {
 											})}
 										/>
 									</div>
+									<div className="space-y-2">
+										<Label htmlFor="compile-debug-cmd" className="text-sm font-medium">
+											Debug Compile Command
+											<CommandInputTooltip />
+										</Label>
+										<Input
+											id="compile-debug-cmd"
+											placeholder="Optional, e.g., g++ -g -O0 -o main %SRC"
+											autoComplete="off"
+											autoCorrect="off"
+											value={changeset.language[selectedLanguageName]!.cmd_compile_debug ?? ""}
+											onInput={e => setChangeset((draft) => {
+												const value = e.currentTarget.value
+												if (value.trim().length === 0) {
+													draft.language![selectedLanguageName]!.cmd_compile_debug = null
+												}
+												else {
+													draft.language![selectedLanguageName]!.cmd_compile_debug = value
+												}
+											})}
+										/>
+									</div>
+									<div className="space-y-2">
+										<div className="flex items-center justify-between">
+											<Label className="text-sm font-medium">
+												Compile Profiles
+												<CommandInputTooltip />
+											</Label>
+											<Button variant="outline" size="sm" className="h-8 w-8 p-0" onClick={handleAddCompileProfile}>
+												<LucidePlusSquare className="h-4 w-4" />
+											</Button>
+										</div>
+										{Object.entries(changeset.language[selectedLanguageName]!.compile_profiles ?? {}).map(([name, command], index) => (
+											// eslint-disable-next-line react/no-array-index-key
+											<div className="flex gap-2" key={index}>
+												<Input
+													className="w-40"
+													placeholder="Name"
+													autoComplete="off"
+													autoCorrect="off"
+													value={name}
+													onInput={e => handleRenameCompileProfile(name, e.currentTarget.value)}
+												/>
+												<Input
+													className="flex-1"
+													placeholder="e.g., g++ -g -fsanitize=address,undefined -o main %SRC"
+													autoComplete="off"
+													autoCorrect="off"
+													value={command ?? ""}
+													onInput={e => setChangeset((draft) => {
+														draft.language![selectedLanguageName]!.compile_profiles![name] = e.currentTarget.value
+													})}
+												/>
+												<Button variant="outline" size="sm" className="h-9 w-9 p-0" onClick={() => handleRemoveCompileProfile(name)}>
+													<LucideTrash className="h-4 w-4" />
+												</Button>
+											</div>
+										))}
+									</div>
 									<div className="space-y-2">
 										<Label htmlFor="run-cmd" className="text-sm font-medium">Run Command</Label>
 										<Input
@@ -291,6 +393,76 @@ export function CompilerSection() {
 
 							<Separator />
 
+							{/* Build Steps */}
+							<div className="space-y-4">
+								<div className="flex items-center justify-between">
+									<h4 className="text-sm font-semibold tracking-wide text-muted-foreground uppercase">Build Steps</h4>
+									<Button variant="outline" size="sm" className="h-8 w-8 p-0" onClick={handleAddBuildStep}>
+										<LucidePlusSquare className="h-4 w-4" />
+									</Button>
+								</div>
+								<p className="text-sm text-muted-foreground">Run in order after the compile command, the build stops at the first failed step.</p>
+								{(changeset.language[selectedLanguageName]!.build_steps ?? []).map((step, index) => (
+									// eslint-disable-next-line react/no-array-index-key
+									<div className="space-y-2 rounded-md border p-3" key={`${selectedLanguageName}-${index}`}>
+										<div className="flex gap-2">
+											<Input
+												className="w-40"
+												placeholder="Name"
+												autoComplete="off"
+												autoCorrect="off"
+												value={step.name}
+												onInput={e => handleUpdateBuildStep(index, (draft) => { draft.name = e.currentTarget.value })}
+											/>
+											<Input
+												className="flex-1"
+												placeholder="e.g., jar cfe main.jar Main Main.class"
+												autoComplete="off"
+												autoCorrect="off"
+												value={step.command}
+												onInput={e => handleUpdateBuildStep(index, (draft) => { draft.command = e.currentTarget.value })}
+											/>
+											<Input
+												className="w-32"
+												type="number"
+												min={1}
+												placeholder="Timeout (ms)"
+												value={step.timeout_millis ?? ""}
+												onInput={(e) => {
+													const value = e.currentTarget.value
+													handleUpdateBuildStep(index, (draft) => { draft.timeout_millis = value === "" ? null : Math.max(1, Number(value)) })
+												}}
+											/>
+											<Button variant="outline" size="sm" className="h-9 w-9 p-0" onClick={() => handleRemoveBuildStep(index)}>
+												<LucideTrash className="h-4 w-4" />
+											</Button>
+										</div>
+										<Textarea
+											placeholder="Environment variables, one KEY=VALUE per line"
+											defaultValue={Object.entries(step.env ?? {}).map(([key, value]) => `${key}=${value}`).join("\n")}
+											onBlur={(e) => {
+												const lines = e.currentTarget.value.split("\n").filter(line => line.includes("="))
+												handleUpdateBuildStep(index, (draft) => {
+													draft.env = Object.fromEntries(lines.map(line => [line.slice(0, line.indexOf("=")).trim(), line.slice(line.indexOf("=") + 1)]))
+												})
+											}}
+										/>
+										<Input
+											placeholder="Expected artifacts separated by spaces, e.g., main.jar"
+											autoComplete="off"
+											autoCorrect="off"
+											defaultValue={(step.artifacts ?? []).join(" ")}
+											onBlur={(e) => {
+												const artifacts = e.currentTarget.value.split(/\s+/).filter(artifact => artifact.length > 0)
+												handleUpdateBuildStep(index, (draft) => { draft.artifacts = artifacts })
+											}}
+										/>
+									</div>
+								))}
+							</div>
+
+							<Separator />
+
 							{/* Execution Hooks */}
 							<div className="space-y-4">
 								<h4 className="text-sm font-semibold tracking-wide text-muted-foreground uppercase">Execution Hooks</h4>
@@ -339,6 +511,51 @@ export function CompilerSection() {
 											})}
 										/>
 									</div>
+									<div className="flex items-center justify-between">
+										<Label htmlFor="sandbox" className="text-sm font-medium">
+											Run in Sandbox (Linux only)
+										</Label>
+										<Switch
+											id="sandbox"
+											checked={changeset.language[selectedLanguageName]!.sandbox ?? false}
+											onCheckedChange={value => setChangeset((draft) => {
+												draft.language![selectedLanguageName]!.sandbox = value
+											})}
+										/>
+									</div>
+									<div className="grid grid-cols-2 gap-4">
+										<div className="space-y-2">
+											<Label htmlFor="time-multiplier" className="text-sm font-medium">
+												Time Limit Multiplier
+											</Label>
+											<Input
+												id="time-multiplier"
+												type="number"
+												min={0.1}
+												step={0.1}
+												value={changeset.language[selectedLanguageName]!.time_multiplier ?? 1}
+												onInput={e => setChangeset((draft) => {
+													const value = Number(e.currentTarget.value)
+													draft.language![selectedLanguageName]!.time_multiplier = value > 0 ? value : 1
+												})}
+											/>
+										</div>
+										<div className="space-y-2">
+											<Label htmlFor="time-extra" className="text-sm font-medium">
+												Extra Time (ms)
+											</Label>
+											<Input
+												id="time-extra"
+												type="number"
+												min={0}
+												value={changeset.language[selectedLanguageName]!.time_extra_millis ?? 0}
+												onInput={e => setChangeset((draft) => {
+													draft.language![selectedLanguageName]!.time_extra_millis = Math.max(0, Math.floor(Number(e.currentTarget.value)))
+												})}
+											/>
+										</div>
+									</div>
+									<p className="text-sm text-muted-foreground">The time limit of a problem is multiplied first, then the extra time is added.</p>
 								</div>
 							</div>
 
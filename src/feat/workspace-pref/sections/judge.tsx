@@ -0,0 +1,43 @@
+import { PrefsItem, PrefsSection } from "@/components/prefs"
+import { Input } from "@/components/ui/input"
+import { Switch } from "@/components/ui/switch"
+import { useWorkspacePrefsChangeset, useWorkspacePrefsChangesetApply, useWorkspacePrefsChangesetSetter } from "../workspace-prefs-changeset-context"
+
+export function JudgeSection() {
+	const changeset = useWorkspacePrefsChangeset()!
+	const updateChangeset = useWorkspacePrefsChangesetSetter()!
+	const applyChangeset = useWorkspacePrefsChangesetApply()!
+	return (
+		<PrefsSection section="Judge">
+			<PrefsItem name="Concurrency" description="How many testcases are judged at the same time when running all of them. Running too many at once makes the measured time unreliable.">
+				<Input
+					type="number"
+					min={1}
+					max={64}
+					value={changeset.judge_concurrency}
+					onInput={e => updateChangeset((draft) => { draft.judge_concurrency = Math.max(1, Number(e.currentTarget.value)) })}
+					onBlur={() => applyChangeset()}
+				/>
+			</PrefsItem>
+			<PrefsItem name="Output Limit (MiB)" description="A program is killed as soon as it writes more than this to stdout.">
+				<Input
+					type="number"
+					min={1}
+					value={changeset.output_limit_mib}
+					onInput={e => updateChangeset((draft) => { draft.output_limit_mib = Math.max(1, Number(e.currentTarget.value)) })}
+					onBlur={() => applyChangeset()}
+				/>
+			</PrefsItem>
+			<PrefsItem name="Capture Backtrace" description="Run a testcase ending with a runtime error again under gdb and show where it crashed. Requires gdb, set a debug compile command for the language to see the source lines.">
+				<Switch
+					checked={changeset.capture_backtrace}
+					onCheckedChange={
+						value => updateChangeset((draft) => {
+							draft.capture_backtrace = value
+						}, true)
+					}
+				/>
+			</PrefsItem>
+		</PrefsSection>
+	)
+}
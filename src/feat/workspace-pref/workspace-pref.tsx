@@ -13,6 +13,7 @@ import { useWorkspaceConfig } from "@/hooks/use-workspace-config"
 import { useWorkspaceConfigMutation } from "@/hooks/use-workspace-config-mutation"
 import { CompilerSection } from "./sections/compiler"
 import { EditorSection } from "./sections/editor"
+import { JudgeSection } from "./sections/judge"
 import { StorageSection } from "./sections/storage"
 import { WorkspacePrefsChangesetApplyContext, WorkspacePrefsChangesetContext, WorkspacePrefsChangesetSetterContext } from "./workspace-prefs-changeset-context"
 
@@ -94,6 +95,7 @@ export function WorkspacePref() {
 							<ScrollArea className="flex-1">
 								<EditorSection />
 								<CompilerSection />
+								<JudgeSection />
 								<StorageSection />
 							</ScrollArea>
 						</PrefsProvider>
@@ -0,0 +1,9 @@
+import { useQuery } from "@tanstack/react-query"
+import { commands } from "@/lib/client"
+
+export function useCheckers() {
+	return useQuery({
+		queryKey: ["checker-programs"],
+		queryFn: () => commands.getCheckers(),
+	})
+}
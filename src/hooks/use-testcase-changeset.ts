@@ -0,0 +1,30 @@
+import type { TestCaseChangeset } from "@/lib/client"
+import { useMutation, useQueryClient } from "@tanstack/react-query"
+import { commands } from "@/lib/client"
+import { testcaseQueryKeyOf } from "./use-testcases"
+
+export const REMOVE_RECIPE_CHANGESET: TestCaseChangeset = {
+	generator: null,
+	generator_args: null,
+	reference: null,
+}
+
+export function useTestcaseChangeset(problemID: string) {
+	const queryClient = useQueryClient()
+	return useMutation({
+		mutationFn: async ({
+			id,
+			changeset,
+		}: {
+			id: string
+			changeset: TestCaseChangeset
+		}) => {
+			return await commands.updateTestcase(id, changeset)
+		},
+		onSuccess: () => {
+			queryClient.invalidateQueries({
+				queryKey: testcaseQueryKeyOf(problemID),
+			})
+		},
+	})
+}
@@ -1,5 +1,5 @@
 import type { Text } from "yjs"
-import type { ProgramConfig, WorkspaceConfig } from "../client"
+import type { Diagnostic, ProgramConfig, WorkspaceConfig } from "../client"
 // eslint-disable-next-line ts/consistent-type-definitions
 export type AlgorimejoEvents = {
 	ready: void
@@ -21,6 +21,8 @@ export type AlgorimejoEvents = {
 	documentChanged: { documentID: string, ytext: Text, language: string }
 	documentChangedDebounced: { documentID: string, ytext: Text, language: string }
 	solutionDocumentChangedDebounced: { problemID: string, solutionID: string, documentID: string, ytext: Text, language: string }
+	/** Diagnostics of the last compilation of the document, an empty list clears them */
+	compileDiagnostics: { documentID: string, diagnostics: Diagnostic[] }
 	workspaceConfigChanged: { config: WorkspaceConfig }
 	programConfigChanged: { config: ProgramConfig }
 }
@@ -35,6 +35,9 @@ async createSolution(problemId: string, params: CreateSolutionParams) : Promise<
 async createChecker(params: CreateCheckerParams) : Promise<CreateCheckerResult> {
     return await TAURI_INVOKE("create_checker", { params });
 },
+async getCheckers() : Promise<Checker[]> {
+    return await TAURI_INVOKE("get_checkers");
+},
 async getSolution(solutionId: string) : Promise<Solution> {
     return await TAURI_INVOKE("get_solution", { solutionId });
 },
@@ -56,6 +59,12 @@ async createTestcase(problemId: string) : Promise<TestCase> {
 async deleteTestcase(testcaseId: string) : Promise<null> {
     return await TAURI_INVOKE("delete_testcase", { testcaseId });
 },
+/**
+ * Set the generator recipe of a testcase, the input and the answer are not regenerated
+ */
+async updateTestcase(testcaseId: string, params: TestCaseChangeset) : Promise<null> {
+    return await TAURI_INVOKE("update_testcase", { testcaseId, params });
+},
 async getTestcases(problemId: string) : Promise<TestCase[]> {
     return await TAURI_INVOKE("get_testcases", { problemId });
 },
@@ -98,8 +107,8 @@ async killLanguageServer(pid: string) : Promise<null> {
 async sendMessageToLanguageServer(pid: string, message: string) : Promise<null> {
     return await TAURI_INVOKE("send_message_to_language_server", { pid, message });
 },
-async executeProgramCallback(taskTag: string, commands: string, env: Partial<{ [key in string]: string }>, inputFilename: string, timeoutMillis: number) : Promise<ProgramOutput> {
-    return await TAURI_INVOKE("execute_program_callback", { taskTag, commands, env, inputFilename, timeoutMillis });
+async executeProgramCallback(taskTag: string, commands: string, env: Partial<{ [key in string]: string }>, inputFilename: string, timeoutMillis: number, memoryLimitKib: number | null) : Promise<ProgramOutput> {
+    return await TAURI_INVOKE("execute_program_callback", { taskTag, commands, env, inputFilename, timeoutMillis, memoryLimitKib });
 },
 async writeFileToTaskTag(taskTag: string, filename: string, content: string) : Promise<string> {
     return await TAURI_INVOKE("write_file_to_task_tag", { taskTag, filename, content });
@@ -107,8 +116,83 @@ async writeFileToTaskTag(taskTag: string, filename: string, content: string) : P
 async executeProgram(taskTag: string, commands: string, env: Partial<{ [key in string]: string }>, timeoutMillis: number) : Promise<ProgramSimpleOutput> {
     return await TAURI_INVOKE("execute_program", { taskTag, commands, env, timeoutMillis });
 },
+/**
+ * Run a program in a terminal, which is kept open after the program exits
+ */
 async executeProgramDetached(taskTag: string, command: string, env: Partial<{ [key in string]: string }>) : Promise<null> {
     return await TAURI_INVOKE("execute_program_detached", { taskTag, command, env });
+},
+/**
+ * Kill the programs started with the task tag, the pending commands of the task
+ * resolve with a cancelled outcome. Returns false if no such task is running.
+ */
+async cancelTask(taskTag: string) : Promise<boolean> {
+    return await TAURI_INVOKE("cancel_task", { taskTag });
+},
+/**
+ * Compile a solution in the working directory of `task_tag`, so it can be run there.
+ * Returns the verdict when the compilation fails, an unchanged solution is not compiled again.
+ */
+async compileSolution(taskTag: string, solutionId: string, profile: string | null) : Promise<Verdict | null> {
+    return await TAURI_INVOKE("compile_solution", { taskTag, solutionId, profile });
+},
+async judgeTestcase(taskTag: string, solutionId: string, testcaseId: string, profile: string | null) : Promise<Verdict> {
+    return await TAURI_INVOKE("judge_testcase", { taskTag, solutionId, testcaseId, profile });
+},
+/**
+ * Judge the solution against every testcase of its problem, the solution is compiled only once.
+ * Results are streamed by `JudgeProgressEvent` as soon as each testcase is finished,
+ * the command returns after all testcases are judged.
+ */
+async judgeAllTestcases(taskTag: string, solutionId: string, profile: string | null) : Promise<null> {
+    return await TAURI_INVOKE("judge_all_testcases", { taskTag, solutionId, profile });
+},
+/**
+ * Recorded runs of a solution or a testcase, the newest first
+ */
+async getRuns(params: GetRunsParams) : Promise<Run[]> {
+    return await TAURI_INVOKE("get_runs", { params });
+},
+/**
+ * The output a recorded run kept, `None` if the solution is not run
+ */
+async getRunOutput(runId: string) : Promise<string | null> {
+    return await TAURI_INVOKE("get_run_output", { runId });
+},
+/**
+ * When the solution is last run and last accepted, on a testcase or on any of them
+ */
+async getRunStatus(solutionId: string, testcaseId: string | null) : Promise<RunStatus> {
+    return await TAURI_INVOKE("get_run_status", { solutionId, testcaseId });
+},
+/**
+ * Run the validator of the problem over the input of every testcase,
+ * returns the validation of each testcase in order.
+ */
+async validateTestcases(taskTag: string, problemId: string) : Promise<TestcaseValidation[]> {
+    return await TAURI_INVOKE("validate_testcases", { taskTag, problemId });
+},
+/**
+ * Stress test a solution against a reference solution on inputs printed by a generator,
+ * all three are solutions of the same problem. Passed iterations are streamed by `StressProgressEvent`.
+ * The first failing input is saved as a new testcase, with the output of the reference solution as the answer.
+ */
+async stressTest(taskTag: string, params: StressTestParams) : Promise<StressTestReport> {
+    return await TAURI_INVOKE("stress_test", { taskTag, params });
+},
+/**
+ * Shrink the input of a testcase on which the solution fails by removing lines and tokens,
+ * the smallest input on which it still fails is saved as a new testcase of the same problem.
+ */
+async minimizeTestcase(taskTag: string, params: MinimizeTestcaseParams) : Promise<MinimizeTestcaseReport> {
+    return await TAURI_INVOKE("minimize_testcase", { taskTag, params });
+},
+/**
+ * Run the generator recipe of a testcase, the generated input and the answer
+ * printed by its reference solution replace the contents of the testcase.
+ */
+async regenerateTestcase(taskTag: string, testcaseId: string) : Promise<GenerateOutcome> {
+    return await TAURI_INVOKE("regenerate_testcase", { taskTag, testcaseId });
 }
 }
 
@@ -116,17 +200,21 @@ async executeProgramDetached(taskTag: string, command: string, env: Partial<{ [k
 
 
 export const events = __makeEvents__<{
+judgeProgressEvent: JudgeProgressEvent,
 languageServerEvent: LanguageServerEvent,
 programConfigUpdateEvent: ProgramConfigUpdateEvent,
 programOutputEvent: ProgramOutputEvent,
 queryClientInvalidateEvent: QueryClientInvalidateEvent,
+stressProgressEvent: StressProgressEvent,
 toastEvent: ToastEvent,
 workspaceConfigUpdateEvent: WorkspaceConfigUpdateEvent
 }>({
+judgeProgressEvent: "judge-progress-event",
 languageServerEvent: "language-server-event",
 programConfigUpdateEvent: "program-config-update-event",
 programOutputEvent: "program-output-event",
 queryClientInvalidateEvent: "query-client-invalidate-event",
+stressProgressEvent: "stress-progress-event",
 toastEvent: "toast-event",
 workspaceConfigUpdateEvent: "workspace-config-update-event"
 })
@@ -137,18 +225,102 @@ workspaceConfigUpdateEvent: "workspace-config-update-event"
 
 /** user-defined types **/
 
-export type AdvLanguageItem = { base: LanguageBase; cmd_compile: string; cmd_before_run: string | null; cmd_after_run: string | null; cmd_run: string; lsp: string | null; lsp_connect: LanguageServerProtocolConnectionType | null; initial_solution_content: string | null }
+export type AdvLanguageItem = { base: LanguageBase; cmd_compile: string; 
+/**
+ * Compile the solution with debug info, used to capture the backtrace of a runtime error
+ */
+cmd_compile_debug?: string | null; 
+/**
+ * Compile commands selectable by name for a run instead of `cmd_compile`
+ */
+compile_profiles?: Partial<{ [key in string]: string }>; 
+/**
+ * Run in order after `cmd_compile`, the build stops at the first failed step
+ */
+build_steps?: BuildStep[]; cmd_before_run: string | null; cmd_after_run: string | null; cmd_run: string; lsp: string | null; lsp_connect: LanguageServerProtocolConnectionType | null; initial_solution_content: string | null; 
+/**
+ * Run the solution in a sandbox, only supported on Linux
+ */
+sandbox?: boolean; 
+/**
+ * The time limit of a problem is multiplied by it for solutions in this language
+ */
+time_multiplier?: number; 
+/**
+ * Added to the time limit after it is multiplied
+ */
+time_extra_millis?: number }
+export type Backtrace = { 
+/**
+ * Innermost first
+ */
+frames: StackFrame[]; 
+/**
+ * `None` if no frame is in the source of the solution
+ */
+crash_site: CrashSite | null }
+/**
+ * A command building the solution after `cmd_compile`, run in the working directory
+ */
+export type BuildStep = { name: string; command: string; 
+/**
+ * The timeout of the compilation is used if not set
+ */
+timeout_millis: number | null; 
+/**
+ * Set for the command, and substituted for `%NAME` in it
+ */
+env?: Partial<{ [key in string]: string }>; 
+/**
+ * Files relative to the working directory which the step must produce
+ */
+artifacts?: string[] }
 export type Checker = { id: string; name: string; language: string; description: string | null; document_id: string; document: Document | null }
+/**
+ * The line of the solution where it crashed
+ */
+export type CrashSite = { 
+/**
+ * 1-based
+ */
+line: number; code: string }
 export type CreateCheckerParams = { name: string; language: string; description: string | null; content: string | null }
 export type CreateCheckerResult = { checker: Checker }
-export type CreateProblemParams = { name: string; url: string | null; group: string | null; statement: string | null; checker: string | null; time_limit: number; memory_limit: number; initial_solution: CreateSolutionParams | null }
+export type CreateProblemParams = { name: string; url: string | null; group: string | null; statement: string | null; checker: string | null; time_limit: number; memory_limit: number; interactive: boolean; input_file: string | null; output_file: string | null; initial_solution: CreateSolutionParams | null }
 export type CreateProblemResult = { problem: Problem }
 export type CreateSolutionParams = { author: string | null; name: string; language: string; content: string | null }
 export type CreateSolutionResult = { solution: Solution }
+export type Diagnostic = { 
+/**
+ * `None` if the diagnostic is in the source of the solution
+ */
+file: string | null; 
+/**
+ * 1-based
+ */
+line: number; 
+/**
+ * 1-based, `None` if the compiler does not report it
+ */
+column: number | null; severity: Severity; message: string }
 export type Document = { id: string; create_datetime: string; modified_datetime: string; filename: string }
+export type GenerateOutcome = { type: "Generated"; input: string; answer: string } | 
+/**
+ * The generator did not exit normally
+ */
+{ type: "GeneratorFailed"; output: ProgramOutput } | 
+/**
+ * The reference solution did not exit normally on the generated input
+ */
+{ type: "ReferenceFailed"; output: ProgramOutput } | { type: "Cancelled" }
 export type GetProblemsParams = { cursor: string | null; limit: number | null; search: string | null; sort_by: GetProblemsSortBy | null; sort_order: SortOrder | null }
 export type GetProblemsResult = { problems: Problem[]; next_cursor: string | null; has_more: boolean }
 export type GetProblemsSortBy = "Name" | "CreateDatetime" | "ModifiedDatetime"
+export type GetRunsParams = { solution_id: string | null; testcase_id: string | null; 
+/**
+ * Only the runs with this result, like `AC`
+ */
+verdict: string | null; limit: number | null }
 /**
  * Supported I/O methods for language server communication
  */
@@ -157,27 +329,312 @@ export type IOMethod =
  * Use standard input/output for communication
  */
 "StdIO"
+/**
+ * Progress of a testcase reported by [`judge_all`]
+ */
+export type JudgeProgress = { type: "Stdout"; line: string } | { type: "Stderr"; line: string } | { type: "Judged"; verdict: Verdict } | { type: "Failed"; error: string }
+export type JudgeProgressEvent = { task_tag: string; testcase_id: string; progress: JudgeProgress }
 export type Keymap = "Default" | "Vim" | "Emacs"
 export type LanguageBase = "Cpp" | "TypeScript" | "JavaScript" | "Go" | "Python" | "Text" | "Unknown"
 export type LanguageServerEvent = { pid: string; response: LanguageServerResponse }
 export type LanguageServerProtocolConnectionType = "StdIO" | "WebSocket"
 export type LanguageServerResponse = { type: "Closed"; exit_code: number } | { type: "Message"; msg: string }
-export type Problem = { id: string; name: string; url: string | null; group: string; statement: string | null; checker: string | null; create_datetime: string; modified_datetime: string; time_limit: number; memory_limit: number; solutions: Solution[] }
-export type ProblemChangeset = { name: string | null; url: string | null; group: string | null; statement: string | null; checker: string | null; time_limit: number | null; memory_limit: number | null }
-export type ProgramConfig = { workspace: string | null; theme: string; system_titlebar: boolean; competitive_companion_addr: string; competitive_companion_enabled: boolean; workspace_history: string[]; keymap: Keymap }
+export type MinimizeOutcome = 
+/**
+ * The smallest failing input found, along with the answer and the verdict on it
+ */
+{ type: "Minimized"; input: string; answer: string; verdict: Verdict; runs: number } | 
+/**
+ * The target solution is accepted on the original input
+ */
+{ type: "NotFailing" } | 
+/**
+ * The reference solution does not exit normally on the original input
+ */
+{ type: "ReferenceFailed" } | { type: "Cancelled" }
+export type MinimizeTestcaseParams = { 
+/**
+ * The solution failing on the testcase
+ */
+solution_id: string; 
+/**
+ * Its output is taken as the answer of every smaller input
+ */
+reference_id: string; testcase_id: string; 
+/**
+ * How many smaller inputs are tried at most
+ */
+max_runs: number }
+export type MinimizeTestcaseReport = { outcome: MinimizeOutcome; 
+/**
+ * The testcase created from the smallest failing input
+ */
+testcase: TestCase | null }
+export type Problem = { id: string; name: string; url: string | null; group: string; statement: string | null; checker: string | null; create_datetime: string; modified_datetime: string; time_limit: number; memory_limit: number; 
+/**
+ * The solution talks with an interactor instead of reading a static input
+ */
+interactive: boolean; 
+/**
+ * Id of the interactor program in the checker table
+ */
+interactor: string | null; 
+/**
+ * Name of the file the solution reads its input from, `None` means stdin
+ */
+input_file: string | null; 
+/**
+ * Name of the file the solution writes its output to, `None` means stdout
+ */
+output_file: string | null; 
+/**
+ * Id of the program in the checker table which validates the testcase inputs
+ */
+validator: string | null; solutions: Solution[] }
+export type ProblemChangeset = { name: string | null; url: string | null; group: string | null; statement: string | null; checker: string | null; time_limit: number | null; memory_limit: number | null; interactive: boolean | null; 
+/**
+ * `null` removes the interactor
+ */
+interactor?: string | null; 
+/**
+ * `null` reads the input from stdin again
+ */
+input_file?: string | null; 
+/**
+ * `null` writes the output to stdout again
+ */
+output_file?: string | null; 
+/**
+ * `null` removes the validator
+ */
+validator?: string | null }
+export type ProgramConfig = { workspace: string | null; theme: string; system_titlebar: boolean; competitive_companion_addr: string; competitive_companion_enabled: boolean; workspace_history: string[]; keymap: Keymap; 
+/**
+ * Opens a terminal running the program appended to it, like `x-terminal-emulator -e`.
+ * Empty opens a new console on Windows.
+ */
+terminal_command: string }
 export type ProgramConfigUpdateEvent = { new: ProgramConfig }
-export type ProgramOutput = { type: "Full"; exit_code: number; is_timeout: boolean; content: string; output_file: string } | { type: "Strip"; exit_code: number; size: number; is_timeout: boolean; content: string; output_file: string }
+export type ProgramOutput = { type: "Full"; exit_code: number; termination: ProgramTermination; usage: ResourceUsage; 
+/**
+ * The time limit the program is run with
+ */
+time_limit_millis: number; content: string; output_file: string } | { type: "Strip"; exit_code: number; size: number; termination: ProgramTermination; usage: ResourceUsage; 
+/**
+ * The time limit the program is run with
+ */
+time_limit_millis: number; content: string; output_file: string }
 export type ProgramOutputEvent = { task_tag: string; source: ProgramOutputSource; line: string }
 export type ProgramOutputSource = "Stdout" | "Stderr"
-export type ProgramSimpleOutput = { exit_code: number; stdout: string; stderr: string; is_timeout: boolean }
+export type ProgramSimpleOutput = { exit_code: number; stdout: string; stderr: string; termination: ProgramTermination; usage: ResourceUsage }
+export type ProgramTermination = "Exited" | "TimeLimitExceeded" | "MemoryLimitExceeded" | 
+/**
+ * Killed as soon as it writes more than the output limit
+ */
+"OutputLimitExceeded" | 
+/**
+ * Killed by the sandbox for making a forbidden system call
+ */
+"SecurityViolation" | 
+/**
+ * Killed by a [`CancelToken`] before it exited
+ */
+"Cancelled"
 export type QueryClientInvalidateEvent = { query_key: string[] | null }
+/**
+ * Resources consumed by a finished program
+ */
+export type ResourceUsage = { wall_time_millis: number; 
+/**
+ * User and system CPU time, not available on every platform
+ */
+cpu_time_millis: number | null; 
+/**
+ * Peak resident memory, not available on every platform
+ */
+peak_memory_kib: number | null }
+/**
+ * A verdict of a solution on a testcase, kept as the history of its runs
+ */
+export type Run = { id: string; solution_id: string; testcase_id: string; 
+/**
+ * Hash of the source the solution is judged with, tells whether it is changed since
+ */
+source_hash: string; 
+/**
+ * Result of the verdict, like `AC`
+ */
+verdict: string; 
+/**
+ * CPU time, or the wall time if it is not available
+ */
+time_millis: number | null; memory_kib: number | null; checker_message: string | null; 
+/**
+ * Name of the file holding the output of the solution in the runs folder of the workspace
+ */
+output_file: string | null; create_datetime: string }
+export type RunStatus = { last_run: Run | null; last_accepted: Run | null; 
+/**
+ * Hash of the current source, which is changed since a run if its `source_hash` differs
+ */
+source_hash: string }
+export type SanitizerReport = { 
+/**
+ * Like `AddressSanitizer`
+ */
+sanitizer: string; message: string; 
+/**
+ * Where it is reported in the source of the solution, 1-based
+ */
+line: number | null; 
+/**
+ * 1-based
+ */
+column: number | null }
+export type Severity = "Error" | "Warning" | "Note"
 export type Solution = { id: string; author: string; name: string; language: string; problem_id: string; document: Document | null }
 export type SolutionChangeset = { name: string | null; author: string | null; language: string | null }
 export type SortOrder = "Asc" | "Desc"
-export type TestCase = { id: string; problem_id: string; input_document_id: string; answer_document_id: string }
+export type StackFrame = { index: number; 
+/**
+ * `??` if gdb can not resolve the symbol
+ */
+function: string; 
+/**
+ * The source file, or the shared library if the frame has no debug info
+ */
+file: string | null; 
+/**
+ * 1-based
+ */
+line: number | null }
+export type StressOutcome = 
+/**
+ * The target solution is not accepted on the input generated with `seed`
+ */
+{ type: "Mismatch"; seed: number; input: string; answer: string; verdict: Verdict } | 
+/**
+ * The generator did not exit normally
+ */
+{ type: "GeneratorFailed"; seed: number; output: ProgramOutput } | 
+/**
+ * The reference solution did not exit normally, so there is no answer to compare with
+ */
+{ type: "ReferenceFailed"; seed: number; output: ProgramOutput } | 
+/**
+ * Every iteration passed before the budget ran out
+ */
+{ type: "Passed"; iterations: number } | { type: "Cancelled"; iterations: number }
+/**
+ * Reported after every iteration passed by the target solution
+ */
+export type StressProgress = { iteration: number; seed: number }
+export type StressProgressEvent = { task_tag: string; progress: StressProgress }
+export type StressTestParams = { 
+/**
+ * The solution to find a failing input for
+ */
+solution_id: string; 
+/**
+ * Prints a random input, the seed is passed as its only argument
+ */
+generator_id: string; 
+/**
+ * Its output is taken as the answer, usually a brute force
+ */
+reference_id: string; first_seed: number; max_iterations: number | null; time_budget_millis: number }
+export type StressTestReport = { outcome: StressOutcome; 
+/**
+ * The testcase created from the failing input
+ */
+testcase: TestCase | null }
+export type TestCase = { id: string; problem_id: string; input_document_id: string; answer_document_id: string; 
+/**
+ * Id of the solution printing the input, `None` if the input is written by hand
+ */
+generator: string | null; 
+/**
+ * Appended to the run command of the generator
+ */
+generator_args: string | null; 
+/**
+ * Id of the solution printing the answer of a generated input
+ */
+reference: string | null }
+export type TestCaseChangeset = { 
+/**
+ * `null` turns the testcase back into a handwritten one
+ */
+generator?: string | null; 
+/**
+ * `null` runs the generator without arguments
+ */
+generator_args?: string | null; 
+/**
+ * `null` removes the reference solution
+ */
+reference?: string | null }
+export type TestcaseValidation = { testcase_id: string; validation: Validation }
 export type ToastEvent = { kind: ToastKind; message: string }
 export type ToastKind = "Info" | "Error" | "Warning" | "Success"
-export type WorkspaceConfig = { font_family: string; font_size: number; language: Partial<{ [key in string]: AdvLanguageItem }>; default_language: string | null; duplicate_save: boolean; duplicate_save_location: string | null }
+/**
+ * Final result of judging a solution against a testcase
+ */
+export type Validation = { result: "VALID" } | 
+/**
+ * The validator exited with a non-zero code: the input violates the constraints, the message tells where
+ */
+{ result: "INVALID"; validator_message: string } | 
+/**
+ * The validator timed out, was killed by a signal or hit a resource limit, so the input could not be checked
+ */
+{ result: "FAIL"; termination: ProgramTermination; validator_message: string } | { result: "CANCELLED" }
+export type Verdict = { result: "CE"; 
+/**
+ * Name of the failed build step, `compile` for `cmd_compile`
+ */
+build_step: string; compiler_stdout: string; compiler_stderr: string; compiler_exit_code: number; 
+/**
+ * Parsed from the output of the compiler
+ */
+diagnostics: Diagnostic[] } | { result: "CETLE" } | { result: "AC"; output: ProgramOutput; checker_message: string } | { result: "WA"; output: ProgramOutput; checker_message: string } | { result: "PE"; output: ProgramOutput; checker_message: string } | 
+/**
+ * Scored by a points checker such as `pointscmp`
+ */
+{ result: "PTS"; output: ProgramOutput; checker_message: string; score: number } | 
+/**
+ * Partially correct, the score is the partial type reported by the checker
+ */
+{ result: "PC"; output: ProgramOutput; checker_message: string; score: number } | { result: "TLE"; output: ProgramOutput } | { result: "MLE"; output: ProgramOutput } | { result: "OLE"; output: ProgramOutput } | 
+/**
+ * The solution made a system call forbidden by the sandbox
+ */
+{ result: "SV"; output: ProgramOutput } | { result: "RE"; output: ProgramOutput; 
+/**
+ * Reported by a sanitizer the solution is built with
+ */
+sanitizer: SanitizerReport | null; 
+/**
+ * Captured by running the testcase again under gdb, if enabled
+ */
+backtrace: Backtrace | null } | { result: "CHKTLE"; output: ProgramOutput; checker_message: string } | { result: "CHKRE"; output: ProgramOutput; checker_message: string } | 
+/**
+ * The task is cancelled before it is judged
+ */
+{ result: "CANCELLED" }
+export type WorkspaceConfig = { font_family: string; font_size: number; language: Partial<{ [key in string]: AdvLanguageItem }>; default_language: string | null; duplicate_save: boolean; duplicate_save_location: string | null; 
+/**
+ * How many testcases are judged at the same time when running all of them
+ */
+judge_concurrency: number; 
+/**
+ * A program is killed once it writes more than this to stdout
+ */
+output_limit_mib: number; 
+/**
+ * Re-run a crashing testcase under gdb to capture the backtrace of the runtime error
+ */
+capture_backtrace: boolean }
 export type WorkspaceConfigUpdateEvent = { new: WorkspaceConfig }
 
 /** tauri-specta globals **/
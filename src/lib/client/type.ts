@@ -15,6 +15,7 @@ export const textLanguageItem: AdvLanguageItem = {
 	lsp: null,
 	lsp_connect: null,
 	initial_solution_content: null,
+	sandbox: false,
 }
 
 export function getLanguageID(language: LanguageBase) {